@@ -0,0 +1,71 @@
+use graphics::bvh::{BvhNode, SplitMethod};
+use graphics::camera::Camera;
+use graphics::hittable_list::HittableList;
+use graphics::image::{AdaptiveSampling, Image, RenderSettings};
+use graphics::sampler::SamplerKind;
+use graphics::scenes::random_scene;
+use graphics::Point3;
+use std::env;
+use vek::vec::Vec3;
+
+fn main() {
+    //image dimensions
+    const ASPECT_RATIO: f64 = 16.0 / 9.0;
+    const IMAGE_WIDTH: usize = 400;
+    const IMAGE_HEIGHT: usize = ((IMAGE_WIDTH as f64) / ASPECT_RATIO) as usize;
+    const MIN_SAMPLES_PER_PIXEL: usize = 16;
+    const MAX_SAMPLES_PER_PIXEL: usize = 512;
+    const MAX_DEPTH: usize = 50;
+
+    //world setup
+
+    let world = BvhNode::new(
+        &random_scene(&None, 0),
+        0.0..0.0,
+        SplitMethod::SurfaceAreaHeuristic,
+    )
+    .expect("every object in the scene has a bounding box");
+
+    //camera setup
+
+    let look_from = Point3::new(13.0, 2.0, 3.0);
+    let look_at = Point3::new(0.0, 0.0, 0.0);
+    let v_up = Vec3::new(0.0, 1.0, 0.0);
+    let dist_to_focus = 10.0;
+    let aperture = 0.1;
+    let cam = Camera::new(
+        look_from,
+        look_at,
+        v_up,
+        20.0,
+        ASPECT_RATIO,
+        aperture,
+        dist_to_focus,
+        None,
+    );
+
+    let mut settings =
+        RenderSettings::new(IMAGE_WIDTH, IMAGE_HEIGHT, MAX_SAMPLES_PER_PIXEL, MAX_DEPTH);
+    settings.sampler = SamplerKind::Sobol;
+    settings.adaptive = Some(AdaptiveSampling {
+        min_samples: MIN_SAMPLES_PER_PIXEL,
+        threshold: 0.02,
+    });
+    let im = Image::render_scene(&settings, cam, world, HittableList::new());
+
+    let counts = im.sample_counts();
+    let total: u64 = counts.iter().map(|&c| c as u64).sum();
+    eprintln!(
+        "{:.1} samples per pixel on average, between {} and {}",
+        total as f64 / counts.len() as f64,
+        counts.iter().min().unwrap(),
+        counts.iter().max().unwrap()
+    );
+    //the first argument, if given, is where to save an image of the sample counts
+    if let Some(path) = env::args().nth(1) {
+        if let Err(e) = im.sample_count_image().save(&path) {
+            eprintln!("could not save {}: {}", path, e);
+        }
+    }
+    println!("{}", im);
+}
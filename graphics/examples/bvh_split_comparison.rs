@@ -0,0 +1,50 @@
+use graphics::bvh::{BvhNode, SplitMethod};
+use graphics::camera::Camera;
+use graphics::hittable_list::HittableList;
+use graphics::image::{Image, RenderSettings};
+use graphics::scenes::random_scene;
+use graphics::Point3;
+use std::time::Instant;
+use vek::vec::Vec3;
+
+fn main() {
+    //image dimensions
+    const ASPECT_RATIO: f64 = 16.0 / 9.0;
+    const IMAGE_WIDTH: usize = 400;
+    const IMAGE_HEIGHT: usize = ((IMAGE_WIDTH as f64) / ASPECT_RATIO) as usize;
+    const SAMPLES_PER_PIXEL: usize = 20;
+    const MAX_DEPTH: usize = 50;
+
+    //world setup
+
+    let scene = random_scene(&None, 0);
+
+    //camera setup
+
+    let look_from = Point3::new(13.0, 2.0, 3.0);
+    let look_at = Point3::new(0.0, 0.0, 0.0);
+    let v_up = Vec3::new(0.0, 1.0, 0.0);
+    let dist_to_focus = 10.0;
+    let aperture = 0.1;
+    let cam = Camera::new(
+        look_from,
+        look_at,
+        v_up,
+        20.0,
+        ASPECT_RATIO,
+        aperture,
+        dist_to_focus,
+        None,
+    );
+
+    let settings = RenderSettings::new(IMAGE_WIDTH, IMAGE_HEIGHT, SAMPLES_PER_PIXEL, MAX_DEPTH);
+
+    eprintln!("{} objects", scene.objects.len());
+    for split in [SplitMethod::Midpoint, SplitMethod::SurfaceAreaHeuristic] {
+        let build_start = Instant::now();
+        let world = BvhNode::new(&scene, 0.0..0.0, split)
+            .expect("every object in the scene has a bounding box");
+        eprintln!("{:?} built in {:?}", split, build_start.elapsed());
+        Image::render_scene(&settings, cam.clone(), world, HittableList::new());
+    }
+}
@@ -1,5 +1,7 @@
+use graphics::bvh::{BvhNode, SplitMethod};
 use graphics::camera::Camera;
-use graphics::image::Image;
+use graphics::hittable_list::HittableList;
+use graphics::image::{Image, RenderSettings};
 use graphics::scenes::random_scene;
 use graphics::Point3;
 use vek::vec::Vec3;
@@ -14,7 +16,12 @@ fn main() {
 
     //world setup
 
-    let world = random_scene(&None);
+    let world = BvhNode::new(
+        &random_scene(&None, 0),
+        0.0..0.0,
+        SplitMethod::SurfaceAreaHeuristic,
+    )
+    .expect("every object in the scene has a bounding box");
 
     //camera setup
 
@@ -34,13 +41,8 @@ fn main() {
         None,
     );
 
-    let im = Image::render_scene(
-        IMAGE_WIDTH,
-        IMAGE_HEIGHT,
-        SAMPLES_PER_PIXEL,
-        MAX_DEPTH,
-        cam,
-        world,
-    );
+    let settings = RenderSettings::new(IMAGE_WIDTH, IMAGE_HEIGHT, SAMPLES_PER_PIXEL, MAX_DEPTH);
+
+    let im = Image::render_scene(&settings, cam, world, HittableList::new());
     println!("{}", im);
 }
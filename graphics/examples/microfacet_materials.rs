@@ -0,0 +1,43 @@
+use graphics::bvh::{BvhNode, SplitMethod};
+use graphics::camera::Camera;
+use graphics::image::{Image, RenderSettings};
+use graphics::scenes::microfacet_spheres;
+use graphics::Point3;
+use vek::vec::Vec3;
+
+fn main() {
+    //image dimensions
+    const ASPECT_RATIO: f64 = 16.0 / 9.0;
+    const IMAGE_WIDTH: usize = 400;
+    const IMAGE_HEIGHT: usize = ((IMAGE_WIDTH as f64) / ASPECT_RATIO) as usize;
+    const SAMPLES_PER_PIXEL: usize = 200;
+    const MAX_DEPTH: usize = 50;
+
+    //world setup
+
+    let (world, lights) = microfacet_spheres();
+    let world = BvhNode::new(&world, 0.0..0.0, SplitMethod::SurfaceAreaHeuristic)
+        .expect("every object in the scene has a bounding box");
+
+    //camera setup
+
+    let look_from = Point3::new(0.0, 3.0, 12.0);
+    let look_at = Point3::new(0.0, 1.0, 0.0);
+    let v_up = Vec3::new(0.0, 1.0, 0.0);
+    let dist_to_focus = 12.0;
+    let aperture = 0.0;
+    let cam = Camera::new(
+        look_from,
+        look_at,
+        v_up,
+        30.0,
+        ASPECT_RATIO,
+        aperture,
+        dist_to_focus,
+        None,
+    );
+
+    let settings = RenderSettings::new(IMAGE_WIDTH, IMAGE_HEIGHT, SAMPLES_PER_PIXEL, MAX_DEPTH);
+    let im = Image::render_scene(&settings, cam, world, lights);
+    println!("{}", im);
+}
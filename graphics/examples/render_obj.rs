@@ -0,0 +1,57 @@
+use graphics::camera::Camera;
+use graphics::hittable::Hittable;
+use graphics::hittable_list::HittableList;
+use graphics::image::{Image, RenderSettings};
+use graphics::material::Lambertian;
+use graphics::obj::Mesh;
+use graphics::Color;
+use std::env;
+use std::process;
+use std::sync::Arc;
+use vek::vec::Vec3;
+
+fn main() {
+    //image dimensions
+    const ASPECT_RATIO: f64 = 16.0 / 9.0;
+    const IMAGE_WIDTH: usize = 400;
+    const IMAGE_HEIGHT: usize = ((IMAGE_WIDTH as f64) / ASPECT_RATIO) as usize;
+    const SAMPLES_PER_PIXEL: usize = 50;
+    const MAX_DEPTH: usize = 50;
+
+    //world setup
+
+    let path = env::args().nth(1).unwrap_or_else(|| {
+        eprintln!("usage: render_obj <file.obj>");
+        process::exit(1);
+    });
+    let default_mat = Arc::new(Lambertian::new(Color::new(0.7, 0.7, 0.7)));
+    let world = Mesh::load(&path, default_mat).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    });
+
+    //camera setup, framing the whole mesh
+
+    let bbox = world.bounding_box(0.0..0.0).unwrap();
+    let look_at = bbox.centroid();
+    let radius = (bbox.max - bbox.min).magnitude() / 2.0;
+    let look_from = look_at + Vec3::new(1.0, 0.6, 2.0).normalized() * radius * 3.0;
+    let v_up = Vec3::new(0.0, 1.0, 0.0);
+    let dist_to_focus = (look_from - look_at).magnitude();
+    let aperture = 0.0;
+    let cam = Camera::new(
+        look_from,
+        look_at,
+        v_up,
+        40.0,
+        ASPECT_RATIO,
+        aperture,
+        dist_to_focus,
+        None,
+    );
+
+    let settings = RenderSettings::new(IMAGE_WIDTH, IMAGE_HEIGHT, SAMPLES_PER_PIXEL, MAX_DEPTH);
+
+    let im = Image::render_scene(&settings, cam, world, HittableList::new());
+    println!("{}", im);
+}
@@ -0,0 +1,77 @@
+use graphics::bvh::{BvhNode, SplitMethod};
+use graphics::camera::Camera;
+use graphics::hittable_list::HittableList;
+use graphics::image::{Image, RenderSettings};
+use graphics::sampler::SamplerKind;
+use graphics::scenes::random_scene;
+use graphics::Point3;
+use vek::vec::Vec3;
+
+fn main() {
+    //image dimensions
+    const ASPECT_RATIO: f64 = 16.0 / 9.0;
+    const IMAGE_WIDTH: usize = 200;
+    const IMAGE_HEIGHT: usize = ((IMAGE_WIDTH as f64) / ASPECT_RATIO) as usize;
+    const SAMPLES_PER_PIXEL: usize = 16;
+    const REFERENCE_SAMPLES_PER_PIXEL: usize = 1024;
+    const MAX_DEPTH: usize = 50;
+
+    //world setup
+
+    let world = BvhNode::new(
+        &random_scene(&None, 0),
+        0.0..0.0,
+        SplitMethod::SurfaceAreaHeuristic,
+    )
+    .expect("every object in the scene has a bounding box");
+
+    //camera setup
+
+    let look_from = Point3::new(13.0, 2.0, 3.0);
+    let look_at = Point3::new(0.0, 0.0, 0.0);
+    let v_up = Vec3::new(0.0, 1.0, 0.0);
+    let dist_to_focus = 10.0;
+    let aperture = 0.1;
+    let cam = Camera::new(
+        look_from,
+        look_at,
+        v_up,
+        20.0,
+        ASPECT_RATIO,
+        aperture,
+        dist_to_focus,
+        None,
+    );
+
+    let mut settings = RenderSettings::new(
+        IMAGE_WIDTH,
+        IMAGE_HEIGHT,
+        REFERENCE_SAMPLES_PER_PIXEL,
+        MAX_DEPTH,
+    );
+    let reference = Image::render_scene(&settings, cam.clone(), world.clone(), HittableList::new());
+
+    //every sampler gets the same budget, and the error against the reference shows how well each converges
+    settings.samples_per_pixel = SAMPLES_PER_PIXEL;
+    settings.seed = 1;
+    for sampler in [
+        SamplerKind::Independent,
+        SamplerKind::Stratified,
+        SamplerKind::Halton,
+        SamplerKind::Sobol,
+    ] {
+        settings.sampler = sampler;
+        let im = Image::render_scene(&settings, cam.clone(), world.clone(), HittableList::new());
+        let squared_error: f64 = im
+            .film()
+            .pixels()
+            .zip(reference.film().pixels())
+            .map(|(a, b)| (a - b).magnitude_squared())
+            .sum();
+        let rmse = (squared_error / (IMAGE_WIDTH * IMAGE_HEIGHT) as f64).sqrt();
+        eprintln!(
+            "{:?}: RMSE {:.5} at {} samples per pixel",
+            sampler, rmse, SAMPLES_PER_PIXEL
+        );
+    }
+}
@@ -1,6 +1,9 @@
 use crate::ray::Ray;
 use crate::Point3;
 
+pub const BOX_PADDING: f64 = 0.0001;
+
+#[derive(Clone, Copy, Debug)]
 pub struct AABB {
     pub min: Point3,
     pub max: Point3,
@@ -11,14 +14,16 @@ impl AABB {
         Self { min, max }
     }
 
-    pub fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> bool {
+    pub fn hit(&self, r: &Ray, mut t_min: f64, mut t_max: f64) -> bool {
         for a in 0..3 {
-            let t0 = ((self.min[a] - r.origin[a]) / r.direction[a])
-                .min((self.max[a] - r.origin[a]) / r.direction[a]);
-            let t1 = ((self.min[a] - r.origin[a]) / r.direction[a])
-                .max((self.max[a] - r.origin[a]) / r.direction[a]);
-            let t_min = t0.max(t_min);
-            let t_max = t1.min(t_max);
+            let inv_d = 1.0 / r.direction[a];
+            let mut t0 = (self.min[a] - r.origin[a]) * inv_d;
+            let mut t1 = (self.max[a] - r.origin[a]) * inv_d;
+            if inv_d < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            t_min = t0.max(t_min);
+            t_max = t1.min(t_max);
             if t_max <= t_min {
                 return false;
             }
@@ -33,11 +38,45 @@ impl AABB {
             (box0.min.z).min(box1.min.z),
         );
         let large = Point3::new(
-            (box0.max.x).min(box1.max.x),
-            (box0.max.y).min(box1.max.y),
-            (box0.max.z).min(box1.max.z),
+            (box0.max.x).max(box1.max.x),
+            (box0.max.y).max(box1.max.y),
+            (box0.max.z).max(box1.max.z),
         );
 
         Self::new(small, large)
     }
+
+    /// Widens any axis thinner than `delta` so that flat primitives never get a degenerate box.
+    /// Primitives use `BOX_PADDING` for `delta`.
+    pub fn padded(self, delta: f64) -> Self {
+        let mut min = self.min;
+        let mut max = self.max;
+        for a in 0..3 {
+            if max[a] - min[a] < delta {
+                min[a] -= delta / 2.0;
+                max[a] += delta / 2.0;
+            }
+        }
+        Self::new(min, max)
+    }
+
+    pub fn centroid(&self) -> Point3 {
+        0.5 * (self.min + self.max)
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        let d = self.max - self.min;
+        2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+    }
+
+    pub fn longest_axis(&self) -> usize {
+        let d = self.max - self.min;
+        if d.x > d.y && d.x > d.z {
+            0
+        } else if d.y > d.z {
+            1
+        } else {
+            2
+        }
+    }
 }
@@ -0,0 +1,258 @@
+use crate::aabb::AABB;
+use crate::hittable::{HitRecord, Hittable};
+use crate::hittable_list::HittableList;
+use crate::ray::Ray;
+use crate::Point3;
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+
+const SAH_BUCKETS: usize = 12;
+const SAH_TRAVERSAL_COST: f64 = 0.125;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SplitMethod {
+    /// Splits at the midpoint of the longest axis of the centroid bounds.
+    Midpoint,
+    /// Splits where the bucketed surface area heuristic estimates the cheapest traversal.
+    SurfaceAreaHeuristic,
+}
+
+#[derive(Debug)]
+pub enum BvhError {
+    /// The list had no objects to build over.
+    Empty,
+    /// The object at this index in the list has no bounding box, like an infinite plane would.
+    Unbounded(usize),
+}
+
+impl fmt::Display for BvhError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "cannot build a BVH over no objects"),
+            Self::Unbounded(i) => write!(f, "object {} has no bounding box", i),
+        }
+    }
+}
+
+impl Error for BvhError {}
+
+#[derive(Clone)]
+pub struct BvhNode {
+    left: Arc<dyn Hittable + Send + Sync>,
+    right: Arc<dyn Hittable + Send + Sync>,
+    bbox: AABB,
+}
+
+struct Primitive {
+    object: Arc<dyn Hittable + Send + Sync>,
+    bbox: AABB,
+    centroid: Point3,
+}
+
+impl BvhNode {
+    /// Builds a hierarchy over every object in `list`, which must be non-empty and hold only
+    /// objects with bounding boxes.
+    pub fn new(
+        list: &HittableList,
+        time: Range<f64>,
+        split: SplitMethod,
+    ) -> Result<Self, BvhError> {
+        if list.objects.is_empty() {
+            return Err(BvhError::Empty);
+        }
+        let mut primitives = list
+            .objects
+            .iter()
+            .enumerate()
+            .map(|(i, object)| {
+                let bbox = object
+                    .bounding_box(time.clone())
+                    .ok_or(BvhError::Unbounded(i))?;
+                Ok(Primitive {
+                    object: object.clone(),
+                    bbox,
+                    centroid: bbox.centroid(),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self::build(&mut primitives, split))
+    }
+
+    fn build(primitives: &mut [Primitive], split: SplitMethod) -> Self {
+        let (left, right): (
+            Arc<dyn Hittable + Send + Sync>,
+            Arc<dyn Hittable + Send + Sync>,
+        ) = match primitives.len() {
+            1 => (primitives[0].object.clone(), primitives[0].object.clone()),
+            2 => (primitives[0].object.clone(), primitives[1].object.clone()),
+            _ => {
+                let mid = match split {
+                    SplitMethod::Midpoint => Self::midpoint_split(primitives),
+                    SplitMethod::SurfaceAreaHeuristic => Self::sah_split(primitives),
+                };
+                let (left, right) = primitives.split_at_mut(mid);
+                (
+                    Self::build_child(left, split),
+                    Self::build_child(right, split),
+                )
+            }
+        };
+        let bbox = primitives
+            .iter()
+            .skip(1)
+            .fold(primitives[0].bbox, |acc, p| {
+                AABB::surrounding_box(acc, p.bbox)
+            });
+        Self { left, right, bbox }
+    }
+
+    fn build_child(
+        primitives: &mut [Primitive],
+        split: SplitMethod,
+    ) -> Arc<dyn Hittable + Send + Sync> {
+        if primitives.len() == 1 {
+            primitives[0].object.clone()
+        } else {
+            Arc::new(Self::build(primitives, split))
+        }
+    }
+
+    fn centroid_bounds(primitives: &[Primitive]) -> AABB {
+        primitives.iter().skip(1).fold(
+            AABB::new(primitives[0].centroid, primitives[0].centroid),
+            |acc, p| AABB::surrounding_box(acc, AABB::new(p.centroid, p.centroid)),
+        )
+    }
+
+    /// Sorts by centroid along `axis` and splits the primitives into two equal halves.
+    fn median_split(primitives: &mut [Primitive], axis: usize) -> usize {
+        primitives.sort_by(|a, b| a.centroid[axis].total_cmp(&b.centroid[axis]));
+        primitives.len() / 2
+    }
+
+    fn midpoint_split(primitives: &mut [Primitive]) -> usize {
+        let bounds = Self::centroid_bounds(primitives);
+        let axis = bounds.longest_axis();
+        let midpoint = bounds.centroid()[axis];
+
+        let mut mid = 0;
+        for i in 0..primitives.len() {
+            if primitives[i].centroid[axis] < midpoint {
+                primitives.swap(i, mid);
+                mid += 1;
+            }
+        }
+        //every centroid landed on one side, so fall back to splitting by count
+        if mid == 0 || mid == primitives.len() {
+            Self::median_split(primitives, axis)
+        } else {
+            mid
+        }
+    }
+
+    fn sah_split(primitives: &mut [Primitive]) -> usize {
+        let bounds = Self::centroid_bounds(primitives);
+        let axis = bounds.longest_axis();
+        let extent = bounds.max[axis] - bounds.min[axis];
+        if extent <= 0.0 {
+            return Self::median_split(primitives, axis);
+        }
+
+        let bucket_of = |p: &Primitive| {
+            let b = (SAH_BUCKETS as f64 * (p.centroid[axis] - bounds.min[axis]) / extent) as usize;
+            b.min(SAH_BUCKETS - 1)
+        };
+
+        let mut counts = [0usize; SAH_BUCKETS];
+        let mut boxes: [Option<AABB>; SAH_BUCKETS] = [None; SAH_BUCKETS];
+        for p in primitives.iter() {
+            let b = bucket_of(p);
+            counts[b] += 1;
+            boxes[b] = Some(match boxes[b] {
+                Some(bbox) => AABB::surrounding_box(bbox, p.bbox),
+                None => p.bbox,
+            });
+        }
+
+        //sweep from the right so that each candidate split can read its right-hand cost directly
+        let mut right_area = [0.0; SAH_BUCKETS];
+        let mut right_count = [0usize; SAH_BUCKETS];
+        let mut acc_box: Option<AABB> = None;
+        let mut acc_count = 0;
+        for b in (1..SAH_BUCKETS).rev() {
+            acc_box = merge(acc_box, boxes[b]);
+            acc_count += counts[b];
+            right_area[b] = acc_box.map_or(0.0, |bbox| bbox.surface_area());
+            right_count[b] = acc_count;
+        }
+
+        let total_area = primitives
+            .iter()
+            .skip(1)
+            .fold(primitives[0].bbox, |acc, p| {
+                AABB::surrounding_box(acc, p.bbox)
+            })
+            .surface_area();
+
+        let mut best_cost = f64::INFINITY;
+        let mut best_bucket = 0;
+        let mut acc_box: Option<AABB> = None;
+        let mut acc_count = 0;
+        for b in 0..SAH_BUCKETS - 1 {
+            acc_box = merge(acc_box, boxes[b]);
+            acc_count += counts[b];
+            if acc_count == 0 || right_count[b + 1] == 0 {
+                continue;
+            }
+            let left_area = acc_box.map_or(0.0, |bbox| bbox.surface_area());
+            let cost = SAH_TRAVERSAL_COST
+                + (acc_count as f64 * left_area + right_count[b + 1] as f64 * right_area[b + 1])
+                    / total_area;
+            if cost < best_cost {
+                best_cost = cost;
+                best_bucket = b;
+            }
+        }
+
+        if best_cost == f64::INFINITY {
+            return Self::median_split(primitives, axis);
+        }
+
+        let mut mid = 0;
+        for i in 0..primitives.len() {
+            if bucket_of(&primitives[i]) <= best_bucket {
+                primitives.swap(i, mid);
+                mid += 1;
+            }
+        }
+        mid
+    }
+}
+
+fn merge(a: Option<AABB>, b: Option<AABB>) -> Option<AABB> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(AABB::surrounding_box(a, b)),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+impl Hittable for BvhNode {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        if !self.bbox.hit(r, t_min, t_max) {
+            return None;
+        }
+
+        let hit_left = self.left.hit(r, t_min, t_max);
+        let t_max = hit_left.as_ref().map_or(t_max, |rec| rec.t);
+        let hit_right = self.right.hit(r, t_min, t_max);
+
+        hit_right.or(hit_left)
+    }
+
+    fn bounding_box(&self, _time: Range<f64>) -> Option<AABB> {
+        Some(self.bbox)
+    }
+}
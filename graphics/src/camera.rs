@@ -1,4 +1,5 @@
 use crate::ray::Ray;
+use crate::sampler::{Sampler, LENS_DIMENSION};
 use crate::utils::*;
 use crate::Point3;
 use std::ops::Range;
@@ -12,12 +13,12 @@ pub struct Camera {
     vertical: Vec3<f64>,
     u: Vec3<f64>,
     v: Vec3<f64>,
-    w: Vec3<f64>,
     lens_radius: f64,
     time: Option<Range<f64>>,
 }
 
 impl Camera {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         look_from: Point3,
         look_at: Point3,
@@ -50,17 +51,17 @@ impl Camera {
             lower_left_corner,
             u,
             v,
-            w,
             lens_radius,
             time,
         }
     }
 
-    pub fn get_ray(&self, s: f64, t: f64) -> Ray {
-        let rd = self.lens_radius * random_in_unit_disk();
+    pub fn get_ray(&self, s: f64, t: f64, sampler: &mut dyn Sampler) -> Ray {
+        sampler.start_dimension(LENS_DIMENSION);
+        let rd = self.lens_radius * random_in_unit_disk(sampler);
         let offset = self.u * rd.x + self.v * rd.y;
         let time = if let Some(t) = &self.time {
-            rand_f64_range(t.start, t.end)
+            t.start + (t.end - t.start) * sampler.time_1d()
         } else {
             0.0
         };
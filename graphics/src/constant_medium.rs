@@ -0,0 +1,94 @@
+use crate::aabb::AABB;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::rng::Rng;
+use std::ops::Range;
+use std::sync::Arc;
+use vek::vec::Vec3;
+
+//guards against boundaries that keep reporting hits at the same point
+const MAX_CROSSINGS: usize = 64;
+const CROSSING_EPSILON: f64 = 1e-6;
+
+/// A volume of uniform density filling the inside of `boundary`.
+///
+/// The boundary does not need to be convex and rays may start inside it; every stretch of the
+/// ray between entering and leaving the boundary counts as being in the medium.
+pub struct ConstantMedium {
+    boundary: Arc<dyn Hittable + Send + Sync>,
+    neg_inv_density: f64,
+    phase_function: Arc<dyn Material + Send + Sync>,
+}
+
+impl ConstantMedium {
+    pub fn new(
+        boundary: Arc<dyn Hittable + Send + Sync>,
+        density: f64,
+        phase_function: Arc<dyn Material + Send + Sync>,
+    ) -> Self {
+        Self {
+            boundary,
+            neg_inv_density: -1.0 / density,
+            phase_function,
+        }
+    }
+}
+
+impl Hittable for ConstantMedium {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        //hit has no generator of its own, so draw from one seeded by the ray. Each ray is already
+        //unique to its pixel and sample, and repeated calls for the same ray agree with each other
+        let mut rng = Rng::from_words(&[
+            r.origin.x.to_bits(),
+            r.origin.y.to_bits(),
+            r.origin.z.to_bits(),
+            r.direction.x.to_bits(),
+            r.direction.y.to_bits(),
+            r.direction.z.to_bits(),
+            r.time.to_bits(),
+        ]);
+        let ray_length = r.direction.magnitude();
+        let mut segment_start = t_min;
+        //whether the ray is inside at t_min is told by which side the next crossing is hit from
+        let mut crossing = self.boundary.hit(r, t_min, f64::INFINITY)?;
+        let mut inside = !crossing.front_face;
+
+        for _ in 0..MAX_CROSSINGS {
+            if segment_start >= t_max {
+                return None;
+            }
+            if inside {
+                let segment_end = crossing.t.min(t_max);
+                //the exponential distribution is memoryless, so each stretch can be sampled on its own
+                let hit_distance = self.neg_inv_density * (1.0 - rng.rand_f64()).ln();
+                let t = segment_start + hit_distance / ray_length;
+                if t < segment_end {
+                    return Some(HitRecord {
+                        p: r.at(t),
+                        normal: Vec3::new(1.0, 0.0, 0.0), //arbitrary
+                        geometric_normal: Vec3::new(1.0, 0.0, 0.0),
+                        tangent: Vec3::new(0.0, 1.0, 0.0),
+                        bitangent: Vec3::new(0.0, 0.0, 1.0),
+                        mat_ptr: self.phase_function.clone(),
+                        t,
+                        u: 0.0,
+                        v: 0.0,
+                        front_face: true, //also arbitrary
+                    });
+                }
+            }
+
+            segment_start = crossing.t;
+            inside = crossing.front_face;
+            crossing = self
+                .boundary
+                .hit(r, crossing.t + CROSSING_EPSILON, f64::INFINITY)?;
+        }
+        None
+    }
+
+    fn bounding_box(&self, time: Range<f64>) -> Option<AABB> {
+        self.boundary.bounding_box(time)
+    }
+}
@@ -0,0 +1,284 @@
+use crate::film::Film;
+use crate::image::Image;
+use crate::Color;
+use exr::prelude::{self as exrs, f16, SmallVec, WritableImage};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PngBitDepth {
+    Eight,
+    Sixteen,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExrPrecision {
+    Half,
+    Float,
+}
+
+/// An extra named pass, such as albedo or normals, written next to the beauty layer of an EXR.
+#[derive(Clone, Copy)]
+pub struct ExrLayer<'a> {
+    pub name: &'a str,
+    pub film: &'a Film,
+}
+
+impl Image {
+    /// Writes the image in the format named by the file extension: `png` (8-bit), `ppm`
+    /// (binary P6), `pfm` (linear float), `exr` (linear half float) or `hdr` (linear RGBE).
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("png") => self.save_png(path, PngBitDepth::Eight),
+            Some("ppm") => self.write_ppm(&mut create(path)?),
+            Some("pfm") => self.write_pfm(&mut create(path)?),
+            Some("exr") => self.save_exr(path, ExrPrecision::Half, &[]),
+            Some("hdr") => self.write_hdr(&mut create(path)?),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unsupported image format: {}", path.display()),
+            )),
+        }
+    }
+
+    pub fn save_png(&self, path: impl AsRef<Path>, bit_depth: PngBitDepth) -> io::Result<()> {
+        self.write_png(create(path.as_ref())?, bit_depth)
+    }
+
+    /// Encodes through the output transform one row at a time.
+    pub fn write_png<W: Write>(&self, w: W, bit_depth: PngBitDepth) -> io::Result<()> {
+        let mut encoder = png::Encoder::new(w, self.width() as u32, self.height() as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(match bit_depth {
+            PngBitDepth::Eight => png::BitDepth::Eight,
+            PngBitDepth::Sixteen => png::BitDepth::Sixteen,
+        });
+        let mut writer = encoder.write_header()?;
+        let mut stream = writer.stream_writer()?;
+
+        let transform = self.output_transform();
+        let mut row_bytes = Vec::new();
+        for row in self.film().rows() {
+            row_bytes.clear();
+            for color in row {
+                match bit_depth {
+                    PngBitDepth::Eight => row_bytes.extend_from_slice(&transform.to_rgb8(color)),
+                    PngBitDepth::Sixteen => {
+                        for channel in transform.to_rgb16(color) {
+                            row_bytes.extend_from_slice(&channel.to_be_bytes());
+                        }
+                    }
+                }
+            }
+            stream.write_all(&row_bytes)?;
+        }
+        stream.finish()?;
+        writer.finish()?;
+        Ok(())
+    }
+
+    /// Binary P6 PPM through the output transform.
+    pub fn write_ppm<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        write!(w, "P6\n{} {}\n255\n", self.width(), self.height())?;
+        let transform = self.output_transform();
+        for color in self.film().pixels() {
+            w.write_all(&transform.to_rgb8(color))?;
+        }
+        w.flush()
+    }
+
+    /// Little-endian PFM of the linear film, skipping the output transform.
+    pub fn write_pfm<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        //a negative scale marks the data as little-endian
+        write!(w, "PF\n{} {}\n-1.0\n", self.width(), self.height())?;
+        //PFM stores its rows from the bottom of the image up
+        for row in self.film().rows().rev() {
+            for color in row {
+                for channel in [color.x, color.y, color.z] {
+                    w.write_all(&(channel as f32).to_le_bytes())?;
+                }
+            }
+        }
+        w.flush()
+    }
+
+    /// OpenEXR of the linear film, skipping the output transform.
+    ///
+    /// `extra_layers` must be the same size as the image. When there are any, the image is
+    /// stored as a multi-layer file with the render in a layer named `beauty`.
+    pub fn save_exr(
+        &self,
+        path: impl AsRef<Path>,
+        precision: ExrPrecision,
+        extra_layers: &[ExrLayer<'_>],
+    ) -> io::Result<()> {
+        let size = (self.width(), self.height());
+        if let Some(layer) = extra_layers
+            .iter()
+            .find(|l| (l.film.width(), l.film.height()) != size)
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "EXR layer {} is {}x{}, expected {}x{}",
+                    layer.name,
+                    layer.film.width(),
+                    layer.film.height(),
+                    size.0,
+                    size.1
+                ),
+            ));
+        }
+
+        //a lone layer stays unnamed so that readers treat it as plain RGB
+        let beauty_attributes = if extra_layers.is_empty() {
+            exrs::LayerAttributes::default()
+        } else {
+            exrs::LayerAttributes::named("beauty")
+        };
+        let mut layers = vec![exr_layer(self.film(), beauty_attributes, precision)];
+        for layer in extra_layers {
+            layers.push(exr_layer(
+                layer.film,
+                exrs::LayerAttributes::named(layer.name),
+                precision,
+            ));
+        }
+
+        let attributes = exrs::ImageAttributes::new(exrs::IntegerBounds::from_dimensions(size));
+        exrs::Image::from_layers(attributes, layers)
+            .write()
+            .to_file(path)
+            .map_err(exr_to_io_error)
+    }
+
+    /// Radiance RGBE of the linear film, skipping the output transform.
+    pub fn write_hdr<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        write!(
+            w,
+            "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+            self.height(),
+            self.width()
+        )?;
+        let width = self.width();
+        //run-length encoded scanlines can only describe widths in this range
+        let run_length_encode = (8..0x8000).contains(&width);
+        let mut channels: [Vec<u8>; 4] = Default::default();
+        for row in self.film().rows() {
+            if !run_length_encode {
+                for color in row {
+                    w.write_all(&to_rgbe(color))?;
+                }
+                continue;
+            }
+
+            for channel in channels.iter_mut() {
+                channel.clear();
+            }
+            for color in row {
+                for (channel, byte) in channels.iter_mut().zip(to_rgbe(color)) {
+                    channel.push(byte);
+                }
+            }
+            w.write_all(&[2, 2, (width >> 8) as u8, (width & 0xff) as u8])?;
+            for channel in &channels {
+                write_rle_channel(w, channel)?;
+            }
+        }
+        w.flush()
+    }
+}
+
+fn exr_layer(
+    film: &Film,
+    attributes: exrs::LayerAttributes,
+    precision: ExrPrecision,
+) -> exrs::Layer<exrs::AnyChannels<exrs::FlatSamples>> {
+    let channel = |name: &str, value: fn(Color) -> f64| {
+        let samples = film.pixels().map(value);
+        let samples = match precision {
+            ExrPrecision::Half => exrs::FlatSamples::F16(samples.map(f16::from_f64).collect()),
+            ExrPrecision::Float => exrs::FlatSamples::F32(samples.map(|c| c as f32).collect()),
+        };
+        exrs::AnyChannel::new(name, samples)
+    };
+    let channels = exrs::AnyChannels::sort(SmallVec::from_vec(vec![
+        channel("R", |c| c.x),
+        channel("G", |c| c.y),
+        channel("B", |c| c.z),
+    ]));
+    exrs::Layer::new(
+        (film.width(), film.height()),
+        attributes,
+        exrs::Encoding::FAST_LOSSLESS,
+        channels,
+    )
+}
+
+fn exr_to_io_error(error: exrs::Error) -> io::Error {
+    match error {
+        exrs::Error::Io(error) => error,
+        error => io::Error::other(error.to_string()),
+    }
+}
+
+/// Shares one exponent between the three channels, as Radiance files do.
+fn to_rgbe(color: Color) -> [u8; 4] {
+    let color = color.map(|c| if c.is_finite() { c.max(0.0) } else { 0.0 });
+    let max = color.reduce_partial_max();
+    if max < 1e-32 {
+        return [0, 0, 0, 0];
+    }
+    //the exponent that puts max in [0.5, 1), as frexp would return
+    let exponent = max.log2().floor() as i32 + 1;
+    let scale = 256.0 / 2f64.powi(exponent);
+    let mantissa = |c: f64| (c * scale).min(255.0) as u8;
+    [
+        mantissa(color.x),
+        mantissa(color.y),
+        mantissa(color.z),
+        (exponent + 128).clamp(0, 255) as u8,
+    ]
+}
+
+/// Writes runs of four or more equal bytes as a count above 128 and everything else as
+/// literal stretches of up to 128 bytes.
+fn write_rle_channel<W: Write>(w: &mut W, data: &[u8]) -> io::Result<()> {
+    const MIN_RUN: usize = 4;
+    const MAX_RUN: usize = 127;
+    const MAX_LITERAL: usize = 128;
+
+    let write_literals = |w: &mut W, literals: &[u8]| -> io::Result<()> {
+        for chunk in literals.chunks(MAX_LITERAL) {
+            w.write_all(&[chunk.len() as u8])?;
+            w.write_all(chunk)?;
+        }
+        Ok(())
+    };
+
+    let mut literal_start = 0;
+    let mut i = 0;
+    while i < data.len() {
+        let mut run = 1;
+        while i + run < data.len() && run < MAX_RUN && data[i + run] == data[i] {
+            run += 1;
+        }
+        if run >= MIN_RUN {
+            write_literals(w, &data[literal_start..i])?;
+            w.write_all(&[(128 + run) as u8, data[i]])?;
+            literal_start = i + run;
+        }
+        i += run;
+    }
+    write_literals(w, &data[literal_start..])
+}
+
+fn create(path: &Path) -> io::Result<BufWriter<File>> {
+    Ok(BufWriter::new(File::create(path)?))
+}
@@ -0,0 +1,146 @@
+use crate::tonemap::{ToneMap, TransferFunction};
+use crate::Color;
+
+//below this the weights of a pixel's samples have all but cancelled out, and dividing by them
+//would only blow up noise
+const MIN_PIXEL_WEIGHT: f64 = 1e-8;
+
+/// Linear, unclamped radiance for every pixel of a render, stored row by row from the top.
+///
+/// Each pixel keeps the weighted sum of the samples that reached it and the sum of their weights,
+/// so samples can be splatted into neighbouring pixels through a reconstruction filter.
+#[derive(Clone)]
+pub struct Film {
+    width: usize,
+    height: usize,
+    color_sums: Vec<Color>,
+    weights: Vec<f64>,
+}
+
+impl Film {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            color_sums: vec![Color::new(0.0, 0.0, 0.0); width * height],
+            weights: vec![0.0; width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The filtered radiance of a pixel, or black if no samples reached it. `y` is counted down
+    /// from the top row.
+    ///
+    /// Filters with negative lobes can leave a pixel with a weight near zero or below it, or
+    /// ring below black next to bright edges, so the result is clamped to be non-negative.
+    pub fn pixel(&self, x: usize, y: usize) -> Color {
+        let i = y * self.width + x;
+        if self.weights[i].abs() < MIN_PIXEL_WEIGHT {
+            Color::new(0.0, 0.0, 0.0)
+        } else {
+            (self.color_sums[i] / self.weights[i]).map(|c| c.max(0.0))
+        }
+    }
+
+    /// Replaces whatever has been accumulated in a pixel with a single sample of weight 1.
+    pub fn set_pixel(&mut self, x: usize, y: usize, color: Color) {
+        let i = y * self.width + x;
+        self.color_sums[i] = color;
+        self.weights[i] = 1.0;
+    }
+
+    pub fn add_sample(&mut self, x: usize, y: usize, color: Color, weight: f64) {
+        let i = y * self.width + x;
+        self.color_sums[i] += color * weight;
+        self.weights[i] += weight;
+    }
+
+    pub fn weight(&self, x: usize, y: usize) -> f64 {
+        self.weights[y * self.width + x]
+    }
+
+    /// Adds everything accumulated in `other` into this film, with the top left of `other` at
+    /// (`x0`, `y0`). Pixels of `other` that fall outside this film are dropped.
+    pub fn merge(&mut self, other: &Film, x0: usize, y0: usize) {
+        let columns = other.width.min(self.width.saturating_sub(x0));
+        for y in 0..other.height.min(self.height.saturating_sub(y0)) {
+            let src = y * other.width;
+            let dst = (y0 + y) * self.width + x0;
+            for x in 0..columns {
+                self.color_sums[dst + x] += other.color_sums[src + x];
+                self.weights[dst + x] += other.weights[src + x];
+            }
+        }
+    }
+
+    /// The filtered radiance of every pixel, from the top row down.
+    pub fn pixels(&self) -> impl Iterator<Item = Color> + '_ {
+        self.rows().flatten()
+    }
+
+    pub fn rows(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = impl Iterator<Item = Color> + '_> + ExactSizeIterator + '_
+    {
+        (0..self.height).map(move |y| (0..self.width).map(move |x| self.pixel(x, y)))
+    }
+}
+
+/// Turns linear film values into display values, applied only when an image is exported.
+#[derive(Clone, Copy, Debug)]
+pub struct OutputTransform {
+    /// Scales the film by `2^exposure` before tone mapping.
+    pub exposure: f64,
+    pub tone_map: ToneMap,
+    pub transfer: TransferFunction,
+}
+
+impl OutputTransform {
+    pub fn new(exposure: f64, tone_map: ToneMap, transfer: TransferFunction) -> Self {
+        Self {
+            exposure,
+            tone_map,
+            transfer,
+        }
+    }
+
+    /// Maps a linear color to display values in [0, 1].
+    pub fn apply(&self, color: Color) -> Color {
+        let exposed = color.map(|c| c.max(0.0)) * 2f64.powf(self.exposure);
+        self.tone_map
+            .apply(exposed)
+            .map(|c| self.transfer.encode(c).clamp(0.0, 1.0))
+    }
+
+    pub fn to_rgb8(&self, color: Color) -> [u8; 3] {
+        let c = self.apply(color);
+        [quantize_u8(c.x), quantize_u8(c.y), quantize_u8(c.z)]
+    }
+
+    pub fn to_rgb16(&self, color: Color) -> [u16; 3] {
+        let c = self.apply(color);
+        [quantize_u16(c.x), quantize_u16(c.y), quantize_u16(c.z)]
+    }
+}
+
+impl Default for OutputTransform {
+    /// No exposure change, a plain clamp and the sRGB curve.
+    fn default() -> Self {
+        Self::new(0.0, ToneMap::default(), TransferFunction::default())
+    }
+}
+
+fn quantize_u8(c: f64) -> u8 {
+    (256.0 * c.clamp(0.0, 0.999)) as u8
+}
+
+fn quantize_u16(c: f64) -> u16 {
+    (65536.0 * c.clamp(0.0, 0.99999)) as u16
+}
@@ -0,0 +1,88 @@
+use std::f64::consts::PI;
+
+/// The reconstruction filter that spreads each sample over the pixels around it.
+///
+/// Offsets and radii are in pixels. Every filter is separable, a product of the same curve in x
+/// and in y. Mitchell and Lanczos have negative lobes, so they sharpen but can ring around very
+/// bright edges.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Filter {
+    /// Equal weight within `radius`. A radius of 0.5 gives each pixel only its own samples.
+    Box { radius: f64 },
+    /// Weight falling off linearly to zero at `radius`.
+    Tent { radius: f64 },
+    /// A Gaussian of standard deviation `sigma`, shifted down so that it reaches zero at `radius`.
+    Gaussian { radius: f64, sigma: f64 },
+    /// The Mitchell-Netravali cubic. B = C = 1/3 is the usual choice.
+    Mitchell { radius: f64, b: f64, c: f64 },
+    /// A sinc windowed by a wider sinc with `tau` lobes.
+    Lanczos { radius: f64, tau: f64 },
+}
+
+impl Filter {
+    pub fn radius(&self) -> f64 {
+        match *self {
+            Filter::Box { radius }
+            | Filter::Tent { radius }
+            | Filter::Gaussian { radius, .. }
+            | Filter::Mitchell { radius, .. }
+            | Filter::Lanczos { radius, .. } => radius,
+        }
+    }
+
+    /// The weight of a sample `(x, y)` pixels away from a pixel center.
+    pub fn evaluate(&self, x: f64, y: f64) -> f64 {
+        self.evaluate_1d(x) * self.evaluate_1d(y)
+    }
+
+    fn evaluate_1d(&self, x: f64) -> f64 {
+        let x = x.abs();
+        if x > self.radius() {
+            return 0.0;
+        }
+        match *self {
+            Filter::Box { .. } => 1.0,
+            Filter::Tent { radius } => radius - x,
+            Filter::Gaussian { radius, sigma } => {
+                (gaussian(x, sigma) - gaussian(radius, sigma)).max(0.0)
+            }
+            Filter::Mitchell { radius, b, c } => mitchell(2.0 * x / radius, b, c),
+            Filter::Lanczos { tau, .. } => sinc(x) * sinc(x / tau),
+        }
+    }
+}
+
+impl Default for Filter {
+    /// A box one pixel wide, which is how samples were always averaged.
+    fn default() -> Self {
+        Filter::Box { radius: 0.5 }
+    }
+}
+
+fn gaussian(x: f64, sigma: f64) -> f64 {
+    (-x * x / (2.0 * sigma * sigma)).exp()
+}
+
+//the cubic for x in [0, 2]
+fn mitchell(x: f64, b: f64, c: f64) -> f64 {
+    if x > 1.0 {
+        ((-b - 6.0 * c) * x * x * x
+            + (6.0 * b + 30.0 * c) * x * x
+            + (-12.0 * b - 48.0 * c) * x
+            + (8.0 * b + 24.0 * c))
+            / 6.0
+    } else {
+        ((12.0 - 9.0 * b - 6.0 * c) * x * x * x
+            + (-18.0 + 12.0 * b + 6.0 * c) * x * x
+            + (6.0 - 2.0 * b))
+            / 6.0
+    }
+}
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-5 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
@@ -1,17 +1,31 @@
 use crate::aabb::AABB;
 use crate::material::Material;
 use crate::ray::Ray;
+use crate::sampler::Sampler;
 use crate::Point3;
 use std::ops::Range;
 use std::sync::Arc;
 use vek::vec::Vec3;
 
+//relative to the size of the hit's coordinates, so it stays above rounding error far out
+const SPAWN_OFFSET: f64 = 1e-9;
+
 #[derive(Clone)]
 pub struct HitRecord {
     pub p: Point3,
+    /// The normal to shade with, facing against the ray. Interpolated vertex normals and
+    /// normal maps can bend it away from the true surface.
     pub normal: Vec3<f64>,
+    /// The true surface normal, on the same side as `normal`.
+    pub geometric_normal: Vec3<f64>,
+    /// How `p` moves as `u` increases. Not normalized.
+    pub tangent: Vec3<f64>,
+    /// How `p` moves as `v` increases. Not normalized.
+    pub bitangent: Vec3<f64>,
     pub mat_ptr: Arc<dyn Material + Send + Sync>,
     pub t: f64,
+    pub u: f64,
+    pub v: f64,
     pub front_face: bool,
 }
 
@@ -24,9 +38,33 @@ impl HitRecord {
             -outward_normal
         }
     }
+
+    /// A ray leaving the hit along `direction`. It starts nudged off the surface on the side it
+    /// leaves from, going by the geometric normal rather than the shading one, so it can't hit
+    /// the surface it starts on or leak through it.
+    pub fn spawn_ray(&self, direction: Vec3<f64>, time: f64) -> Ray {
+        let offset = SPAWN_OFFSET * (1.0 + self.p.map(f64::abs).reduce_partial_max());
+        let offset = if Vec3::dot(direction, self.geometric_normal) >= 0.0 {
+            offset
+        } else {
+            -offset
+        };
+        Ray::new(self.p + offset * self.geometric_normal, direction, time)
+    }
 }
 
 pub trait Hittable {
     fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord>;
     fn bounding_box(&self, time: Range<f64>) -> Option<AABB>;
+
+    /// The density, per unit solid angle seen from `origin`, with which `random` picks
+    /// `direction`. Objects that can't be sampled as lights leave this at 0.
+    fn pdf_value(&self, _origin: &Point3, _direction: &Vec3<f64>) -> f64 {
+        0.0
+    }
+
+    /// A direction from `origin` towards a random point on the object. It need not be normalized.
+    fn random(&self, _origin: &Point3, _sampler: &mut dyn Sampler) -> Vec3<f64> {
+        Vec3::unit_x()
+    }
 }
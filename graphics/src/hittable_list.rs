@@ -1,8 +1,11 @@
 use crate::aabb::AABB;
 use crate::hittable::{HitRecord, Hittable};
 use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::Point3;
 use std::ops::Range;
 use std::sync::Arc;
+use vek::vec::Vec3;
 
 #[derive(Clone)]
 pub struct HittableList {
@@ -23,6 +26,12 @@ impl HittableList {
     }
 }
 
+impl Default for HittableList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Hittable for HittableList {
     fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
         let mut closest_so_far = t_max;
@@ -44,26 +53,36 @@ impl Hittable for HittableList {
         }
 
         let mut output_box: Option<AABB> = None;
-        let mut temp_box: Option<AABB>;
-
-        let mut first_box = true;
         for object in &self.objects {
-            temp_box = object.bounding_box(time.clone());
-            if let None = temp_box {
-                return None;
-            }
-
-            output_box = if first_box {
-                temp_box
-            } else {
-                Some(AABB::surrounding_box(
-                    output_box.unwrap(),
-                    temp_box.unwrap(),
-                ))
-            };
-            first_box = false;
+            let temp_box = object.bounding_box(time.clone())?;
+            output_box = Some(match output_box {
+                Some(output_box) => AABB::surrounding_box(output_box, temp_box),
+                None => temp_box,
+            });
         }
 
         output_box
     }
+
+    //picks one object uniformly, so the density is the average of theirs
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3<f64>) -> f64 {
+        if self.objects.is_empty() {
+            return 0.0;
+        }
+        let sum: f64 = self
+            .objects
+            .iter()
+            .map(|object| object.pdf_value(origin, direction))
+            .sum();
+        sum / self.objects.len() as f64
+    }
+
+    fn random(&self, origin: &Point3, sampler: &mut dyn Sampler) -> Vec3<f64> {
+        if self.objects.is_empty() {
+            return Vec3::unit_x();
+        }
+        let index =
+            ((sampler.get_1d() * self.objects.len() as f64) as usize).min(self.objects.len() - 1);
+        self.objects[index].random(origin, sampler)
+    }
 }
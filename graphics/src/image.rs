@@ -1,11 +1,18 @@
 use crate::camera::Camera;
-use crate::hittable::Hittable;
+use crate::film::{Film, OutputTransform};
+use crate::filter::Filter;
+use crate::hittable::{HitRecord, Hittable};
 use crate::hittable_list::HittableList;
+use crate::material::ScatterData;
+use crate::pdf::{HittablePdf, Pdf};
 use crate::ray::Ray;
+use crate::sampler::{Sampler, SamplerKind};
+use crate::tonemap::{ToneMap, TransferFunction};
 use crate::utils::*;
 use crate::Color;
-use std::sync::Arc;
-use std::sync::Mutex;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
 use std::thread;
 use std::time::Instant;
 
@@ -13,148 +20,583 @@ use std::fmt;
 
 use indicatif::{FormattedDuration, ProgressBar, ProgressStyle};
 
-#[derive(Clone, Copy)]
-struct Pixel {
-    r: u8,
-    g: u8,
-    b: u8,
+/// What a ray sees when it leaves the scene without hitting anything.
+#[derive(Clone, Copy, Debug)]
+pub enum Background {
+    Solid(Color),
+    /// Blends from `bottom` to `top` with the height of the ray direction.
+    Gradient {
+        bottom: Color,
+        top: Color,
+    },
+    Black,
+}
+
+impl Background {
+    pub fn color(&self, r: &Ray) -> Color {
+        match *self {
+            Background::Solid(color) => color,
+            Background::Gradient { bottom, top } => {
+                let unit_direction = unit_vector(r.direction);
+                let t = 0.5 * (unit_direction.y + 1.0);
+                (1.0 - t) * bottom + t * top
+            }
+            Background::Black => Color::new(0.0, 0.0, 0.0),
+        }
+    }
+}
+
+impl Default for Background {
+    /// The blue-white sky used by the first book.
+    fn default() -> Self {
+        Background::Gradient {
+            bottom: Color::new(1.0, 1.0, 1.0),
+            top: Color::new(0.5, 0.7, 1.0),
+        }
+    }
+}
+
+/// Stops sampling a pixel once its value is known well enough, so that flat regions finish early
+/// and the noisy ones get the rest of the budget.
+#[derive(Clone, Copy, Debug)]
+pub struct AdaptiveSampling {
+    /// Samples every pixel takes before it may stop, so the error estimate has something to go on.
+    pub min_samples: usize,
+    /// The largest accepted standard error of a pixel's mean luminance, relative to the mean.
+    pub threshold: f64,
 }
 
+/// How light sampling and BSDF sampling are weighted where both could have found the same light.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MisHeuristic {
+    /// Weights each technique by its share of the summed densities.
+    Balance,
+    /// Like `Balance` with the densities squared, which favours whichever technique is sharper.
+    #[default]
+    Power,
+}
+
+impl MisHeuristic {
+    /// The weight for a sample taken with density `pdf` when the other technique would have
+    /// picked the same direction with density `other_pdf`.
+    pub fn weight(&self, pdf: f64, other_pdf: f64) -> f64 {
+        let (a, b) = match self {
+            MisHeuristic::Balance => (pdf, other_pdf),
+            MisHeuristic::Power => (pdf * pdf, other_pdf * other_pdf),
+        };
+        if a + b > 0.0 {
+            a / (a + b)
+        } else {
+            0.0
+        }
+    }
+}
+
+//pixels darker than this are held to the error they would be allowed at this luminance, so that
+//near-black pixels don't chase a relative error they can never reach
+const DARK_LUMINANCE: f64 = 0.01;
+
 #[derive(Clone)]
-pub struct Image {
-    pixels: Vec<Pixel>,
-    image_height: usize,
-    image_width: usize,
+pub struct RenderSettings {
+    pub image_width: usize,
+    pub image_height: usize,
+    /// With adaptive sampling, the most samples any pixel may take.
+    pub samples_per_pixel: usize,
+    /// Paths are cut off after this many bounces, whatever their throughput.
+    pub max_depth: usize,
+    /// Once a path has bounced this many times, it continues with a probability that follows its
+    /// throughput and the paths that survive are weighted up to make up for the ones that don't.
+    /// This ends dim paths early without darkening the image. `None` follows every path to
+    /// `max_depth`.
+    pub russian_roulette_depth: Option<usize>,
+    pub background: Background,
+    /// Number of worker threads, defaulting to the number of available cores.
+    pub threads: usize,
+    /// Width and height in pixels of the square tiles handed out to the workers.
+    pub tile_size: usize,
+    /// Exposure, tone mapping and display encoding given to the finished image.
+    pub output_transform: OutputTransform,
+    /// Every random number used by the render is derived from this, so the same seed always
+    /// produces the same image whatever the thread count. With a filter wider than a pixel, a
+    /// different tile size can still change the last bits where tiles overlap.
+    pub seed: u64,
+    /// How the sample values for each pixel are spread out.
+    pub sampler: SamplerKind,
+    /// How samples are weighted into the pixels around them.
+    pub filter: Filter,
+    /// When set, each pixel takes between `min_samples` and `samples_per_pixel` samples.
+    pub adaptive: Option<AdaptiveSampling>,
+    pub mis_heuristic: MisHeuristic,
 }
 
-impl Image {
-    pub fn render_scene(
+impl RenderSettings {
+    pub fn new(
         image_width: usize,
         image_height: usize,
         samples_per_pixel: usize,
         max_depth: usize,
+    ) -> Self {
+        Self {
+            image_width,
+            image_height,
+            samples_per_pixel,
+            max_depth,
+            russian_roulette_depth: Some(3),
+            background: Background::default(),
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            tile_size: 16,
+            output_transform: OutputTransform::default(),
+            seed: 0,
+            sampler: SamplerKind::default(),
+            filter: Filter::default(),
+            adaptive: None,
+            mis_heuristic: MisHeuristic::default(),
+        }
+    }
+}
+
+/// A finished render: the linear film plus the transform used to turn it into display output.
+#[derive(Clone)]
+pub struct Image {
+    film: Film,
+    output_transform: OutputTransform,
+    sample_counts: Vec<u32>,
+}
+
+/// A rectangle of pixels `x0..x1` by `y0..y1`, with y counted from the bottom of the image.
+#[derive(Clone, Copy)]
+struct Tile {
+    x0: usize,
+    x1: usize,
+    y0: usize,
+    y1: usize,
+}
+
+impl Tile {
+    /// Covers the image top to bottom, left to right, clipping tiles at the right and bottom edges.
+    fn split_image(image_width: usize, image_height: usize, tile_size: usize) -> Vec<Self> {
+        let mut tiles = Vec::new();
+        for y1 in (1..=image_height).rev().step_by(tile_size) {
+            let y0 = y1.saturating_sub(tile_size);
+            for x0 in (0..image_width).step_by(tile_size) {
+                let x1 = (x0 + tile_size).min(image_width);
+                tiles.push(Self { x0, x1, y0, y1 });
+            }
+        }
+        tiles
+    }
+
+    /// The part of the film that samples from this tile can reach through a filter of `radius`,
+    /// as `(x0, y0, width, height)` with y counted down from the top.
+    fn splat_bounds(&self, radius: f64, image_width: usize, image_height: usize) -> [usize; 4] {
+        let pad = radius.ceil() as usize;
+        let x0 = self.x0.saturating_sub(pad);
+        let x1 = (self.x1 + pad).min(image_width);
+        let y0 = (image_height - self.y1).saturating_sub(pad);
+        let y1 = (image_height - self.y0 + pad).min(image_height);
+        [x0, y0, x1 - x0, y1 - y0]
+    }
+}
+
+impl Image {
+    /// Renders `world` as seen by `cam`. Every hit on a diffuse surface or in a medium also
+    /// samples a point on one of `lights` directly, which should hold the emitters of `world`
+    /// that are worth aiming at. It may be empty.
+    pub fn render_scene<T: Hittable + Sync>(
+        settings: &RenderSettings,
         cam: Camera,
-        world: HittableList,
+        world: T,
+        lights: HittableList,
     ) -> Self {
-        const THREAD_NUM: usize = 4;
-        let image_data: Vec<Vec<Pixel>> = vec![Vec::new(); 4];
-        let id = Arc::new(Mutex::new(image_data));
+        let RenderSettings {
+            image_width,
+            image_height,
+            samples_per_pixel,
+            max_depth,
+            russian_roulette_depth,
+            background,
+            threads,
+            tile_size,
+            output_transform,
+            seed,
+            sampler,
+            filter,
+            adaptive,
+            mis_heuristic,
+        } = *settings;
+
+        let tiles = Tile::split_image(image_width, image_height, tile_size.max(1));
+        let next_tile = AtomicUsize::new(0);
+        let (sender, receiver) = mpsc::channel::<TileResult>();
 
-        let pb = ProgressBar::new(image_height as u64);
+        let pb = ProgressBar::new(tiles.len() as u64);
         pb.set_style(
             ProgressStyle::default_bar()
                 .template("[{elapsed_precise}] [{wide_bar:.cyan/blue}] {percent}% ({eta_precise})"), //idk why the colors aren't working
         );
 
         let beginning = Instant::now();
-        let mut handles = vec![];
-        let mut start = 0;
-        for z in 0..THREAD_NUM {
-            let cam = cam.clone();
-            let world = world.clone();
-            let id = Arc::clone(&id);
-            let pb = pb.clone();
-            let end =
-                (image_height as f64 * (((z + 1) as f64) / THREAD_NUM as f64)).ceil() as usize;
-            let handle = thread::spawn(move || {
-                eprintln!("for z: {}, start: {}, end: {} ", z, start, end);
-                for j in (start..end).rev() {
-                    for i in 0..image_width {
-                        let mut pixel_color = Color::new(0.0, 0.0, 0.0);
-                        for _ in 0..samples_per_pixel {
-                            let rand1: f64 = rand_f64();
-                            let rand2: f64 = rand_f64();
-                            let u: f64 = (i as f64 + rand1) / ((image_width - 1) as f64);
-                            let v: f64 = (j as f64 + rand2) / ((image_height - 1) as f64);
-                            let r = cam.get_ray(u, v);
-                            pixel_color += ray_color(r, &world, max_depth);
-                        }
-                        let pixel = Pixel::write_color(pixel_color, samples_per_pixel);
-                        {
-                            let mut image_data = id.lock().unwrap();
-                            image_data[z].push(pixel);
+        let mut film = Film::new(image_width, image_height);
+        let mut sample_counts = vec![0; image_width * image_height];
+        let context = PathContext {
+            world: &world,
+            lights: &lights,
+            background,
+            max_depth,
+            russian_roulette_depth,
+            mis_heuristic,
+        };
+        thread::scope(|s| {
+            for _ in 0..threads.max(1) {
+                let sender = sender.clone();
+                let (tiles, next_tile, cam, context) = (&tiles, &next_tile, &cam, &context);
+                s.spawn(move || {
+                    let mut sampler = sampler.build(seed, samples_per_pixel);
+                    //each worker keeps pulling the next unrendered tile until none are left
+                    loop {
+                        let tile_index = next_tile.fetch_add(1, Ordering::Relaxed);
+                        let tile = match tiles.get(tile_index) {
+                            Some(&tile) => tile,
+                            None => break,
+                        };
+                        //samples near the edge of the tile spill into its neighbours, so the tile
+                        //gets a film of its own with a border as wide as the filter
+                        let bounds = tile.splat_bounds(filter.radius(), image_width, image_height);
+                        let [film_x0, film_y0, width, height] = bounds;
+                        let mut tile_film = Film::new(width, height);
+                        let mut tile_counts =
+                            Vec::with_capacity((tile.x1 - tile.x0) * (tile.y1 - tile.y0));
+                        for j in (tile.y0..tile.y1).rev() {
+                            for i in tile.x0..tile.x1 {
+                                let pixel_index = (image_height - 1 - j) * image_width + i;
+                                let mut variance = PixelVariance::default();
+                                for sample in 0..samples_per_pixel {
+                                    sampler.start_pixel_sample(pixel_index, sample);
+                                    let offset = sampler.pixel_2d();
+                                    let u: f64 = (i as f64 + offset.x) / ((image_width - 1) as f64);
+                                    let v: f64 =
+                                        (j as f64 + offset.y) / ((image_height - 1) as f64);
+                                    let r = cam.get_ray(u, v, sampler.as_mut());
+                                    let color = ray_color(context, r, sampler.as_mut());
+                                    splat(
+                                        &mut tile_film,
+                                        &filter,
+                                        (i - film_x0) as f64 + offset.x,
+                                        (image_height - j - film_y0) as f64 - offset.y,
+                                        color,
+                                    );
+
+                                    variance.add(luminance(color));
+                                    if let Some(adaptive) = adaptive {
+                                        if variance.count >= adaptive.min_samples
+                                            && variance.relative_error() < adaptive.threshold
+                                        {
+                                            break;
+                                        }
+                                    }
+                                }
+                                tile_counts.push(variance.count as u32);
+                            }
                         }
+                        sender
+                            .send(TileResult {
+                                index: tile_index,
+                                tile,
+                                bounds,
+                                film: tile_film,
+                                sample_counts: tile_counts,
+                            })
+                            .unwrap();
                     }
-                    pb.inc(1);
+                });
+            }
+            drop(sender);
+
+            //only this thread touches the framebuffer. Tiles overlap at their borders, so they
+            //are merged in a fixed order to keep the sums identical from run to run
+            let mut finished = BTreeMap::new();
+            let mut next_merge = 0;
+            for result in receiver {
+                let tile = result.tile;
+                let tile_width = tile.x1 - tile.x0;
+                for (row, j) in (tile.y0..tile.y1).rev().enumerate() {
+                    let start = (image_height - 1 - j) * image_width + tile.x0;
+                    sample_counts[start..start + tile_width].copy_from_slice(
+                        &result.sample_counts[row * tile_width..(row + 1) * tile_width],
+                    );
                 }
-            });
-            start = end;
-            handles.push(handle);
-        }
-        for handle in handles {
-            handle.join().unwrap();
-        }
-        let mut output = Self {
-            pixels: Vec::new(),
-            image_height,
-            image_width,
-        };
-        let image_data = id.lock().unwrap();
-        for i in (0..THREAD_NUM).rev() {
-            for pixl in &image_data[i] {
-                output.pixels.push(*pixl);
+
+                finished.insert(result.index, result);
+                while let Some(result) = finished.remove(&next_merge) {
+                    film.merge(&result.film, result.bounds[0], result.bounds[1]);
+                    next_merge += 1;
+                }
+                pb.inc(1);
             }
-        }
+        });
+
         pb.finish_and_clear();
         eprintln!(
             "Scene rendered in {}",
             FormattedDuration(beginning.elapsed())
         );
-        output
+        Self {
+            film,
+            output_transform,
+            sample_counts,
+        }
     }
-}
 
-impl Pixel {
-    pub fn write_color(pixel_color: Color, samples_per_pixel: usize) -> Self {
-        let mut r = pixel_color.x;
-        let mut g = pixel_color.y;
-        let mut b = pixel_color.z;
+    pub fn width(&self) -> usize {
+        self.film.width()
+    }
 
-        let scale = 1.0 / (samples_per_pixel as f64);
-        r = (scale * r).powf(0.5);
-        g = (scale * g).powf(0.5);
-        b = (scale * b).powf(0.5);
+    pub fn height(&self) -> usize {
+        self.film.height()
+    }
 
-        Self {
-            r: (256.0 * r.clamp(0.0, 0.999)) as u8,
-            g: (256.0 * g.clamp(0.0, 0.999)) as u8,
-            b: (256.0 * b.clamp(0.0, 0.999)) as u8,
+    pub fn film(&self) -> &Film {
+        &self.film
+    }
+
+    pub fn film_mut(&mut self) -> &mut Film {
+        &mut self.film
+    }
+
+    pub fn output_transform(&self) -> OutputTransform {
+        self.output_transform
+    }
+
+    pub fn set_output_transform(&mut self, output_transform: OutputTransform) {
+        self.output_transform = output_transform;
+    }
+
+    /// How many samples each pixel took, from the top row down.
+    pub fn sample_counts(&self) -> &[u32] {
+        &self.sample_counts
+    }
+
+    /// A debug image of `sample_counts`. The film holds the raw counts, and the output transform
+    /// maps the largest count to white.
+    pub fn sample_count_image(&self) -> Image {
+        let mut film = Film::new(self.width(), self.height());
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                let count = self.sample_counts[y * self.width() + x] as f64;
+                film.set_pixel(x, y, Color::broadcast(count));
+            }
+        }
+        let max_count = self.sample_counts.iter().copied().max().unwrap_or(0).max(1);
+        Image {
+            film,
+            output_transform: OutputTransform::new(
+                -(max_count as f64).log2(),
+                ToneMap::Clamp,
+                TransferFunction::Gamma(1.0),
+            ),
+            sample_counts: self.sample_counts.clone(),
         }
     }
-}
 
-impl fmt::Display for Pixel {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{} {} {}", self.r, self.g, self.b)
+    /// The film run through the output transform, as 8-bit RGB triples from the top row down.
+    pub fn to_rgb8(&self) -> Vec<[u8; 3]> {
+        self.film
+            .pixels()
+            .map(|c| self.output_transform.to_rgb8(c))
+            .collect()
     }
 }
 
+/// ASCII P3 PPM, kept for printing to stdout. Prefer `Image::save` for files.
 impl fmt::Display for Image {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let mut output = String::new();
-        output.push_str(&format!(
-            "P3\n{} {}\n 255\n",
-            self.image_width, self.image_height
-        ));
-        for pixel in &self.pixels {
-            output.push_str(&format!("{}\n", pixel));
+        writeln!(f, "P3\n{} {}\n 255", self.width(), self.height())?;
+        let transform = self.output_transform();
+        for color in self.film().pixels() {
+            let [r, g, b] = transform.to_rgb8(color);
+            writeln!(f, "{} {} {}", r, g, b)?;
+        }
+        Ok(())
+    }
+}
+
+/// A finished tile on its way to the main thread.
+struct TileResult {
+    index: usize,
+    tile: Tile,
+    /// Where `film` goes in the image, as returned by `Tile::splat_bounds`.
+    bounds: [usize; 4],
+    film: Film,
+    sample_counts: Vec<u32>,
+}
+
+/// Welford's running mean and variance of a pixel's sample luminances.
+#[derive(Default)]
+struct PixelVariance {
+    count: usize,
+    mean: f64,
+    m2: f64,
+}
+
+impl PixelVariance {
+    fn add(&mut self, x: f64) {
+        self.count += 1;
+        let delta = x - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (x - self.mean);
+    }
+
+    /// The standard error of the mean, relative to the mean.
+    fn relative_error(&self) -> f64 {
+        if self.count < 2 {
+            return f64::INFINITY;
         }
-        write!(f, "{}", output)
+        let variance = self.m2 / (self.count - 1) as f64;
+        (variance / self.count as f64).sqrt() / self.mean.max(DARK_LUMINANCE)
     }
 }
 
-fn ray_color<T: Hittable>(r: Ray, world: &T, depth: usize) -> Color {
-    if depth <= 0 {
-        return Color::new(0.0, 0.0, 0.0);
+/// Adds a sample at `(x, y)`, in pixels from the top left of `film`, to every pixel whose center
+/// is within the filter's reach.
+fn splat(film: &mut Film, filter: &Filter, x: f64, y: f64, color: Color) {
+    let radius = filter.radius();
+    let x_end = ((x + radius).floor() as usize + 1).min(film.width());
+    let y_end = ((y + radius).floor() as usize + 1).min(film.height());
+    for py in (y - radius).floor().max(0.0) as usize..y_end {
+        for px in (x - radius).floor().max(0.0) as usize..x_end {
+            let weight = filter.evaluate(x - (px as f64 + 0.5), y - (py as f64 + 0.5));
+            if weight != 0.0 {
+                film.add_sample(px, py, color, weight);
+            }
+        }
     }
-    if let Some(rec) = world.hit(&r, 0.001, f64::INFINITY) {
-        if let Some(scatter_data) = rec.mat_ptr.scatter(&r, &rec) {
-            return scatter_data.attenuation
-                * ray_color(scatter_data.scattered_ray, world, depth - 1);
+}
+
+/// Everything about a render that stays the same from one path to the next.
+struct PathContext<'a, T> {
+    world: &'a T,
+    lights: &'a HittableList,
+    background: Background,
+    max_depth: usize,
+    russian_roulette_depth: Option<usize>,
+    mis_heuristic: MisHeuristic,
+}
+
+//follows the path bounce by bounce, carrying the product of the attenuations so far
+fn ray_color<T: Hittable>(
+    context: &PathContext<'_, T>,
+    mut ray: Ray,
+    sampler: &mut dyn Sampler,
+) -> Color {
+    let mut color = Color::zero();
+    let mut throughput = Color::one();
+    //the density with which the last hit scattered `ray`, or `None` for the camera ray and
+    //specular bounces, whose emission can't also have been found by sampling a light
+    let mut bsdf_pdf = None;
+
+    for bounce in 0..context.max_depth {
+        let mut rec = match context.world.hit(&ray, 0.001, f64::INFINITY) {
+            Some(rec) => rec,
+            None => {
+                color += throughput * context.background.color(&ray);
+                break;
+            }
+        };
+        if let Some(normal) = rec.mat_ptr.perturbed_normal(&ray, &rec) {
+            rec.normal = normal;
+        }
+
+        let mut emitted = rec.mat_ptr.emitted(rec.u, rec.v, &rec.p);
+        if let Some(bsdf_pdf) = bsdf_pdf {
+            if !emitted.is_approx_zero() {
+                let light_pdf = HittablePdf::new(context.lights, ray.origin).value(&ray.direction);
+                emitted *= context.mis_heuristic.weight(bsdf_pdf, light_pdf);
+            }
+        }
+        color += throughput * emitted;
+
+        sampler.start_bounce(bounce);
+        match rec.mat_ptr.scatter(&ray, &rec, sampler) {
+            Some(ScatterData::Specular {
+                ray: scattered,
+                attenuation,
+            }) => {
+                throughput *= attenuation;
+                ray = scattered;
+                bsdf_pdf = None;
+            }
+            Some(ScatterData::Pdf(bsdf)) => {
+                //draw the bounce before the light sample so each keeps to its own sample dimensions
+                let direction = bsdf.generate(sampler);
+                let scattered_pdf = bsdf.value(&direction);
+
+                let direct = sample_light(context, &ray, &rec, bsdf.as_ref(), bounce, sampler);
+                color += throughput * direct;
+                if scattered_pdf <= 0.0 {
+                    break;
+                }
+                throughput *= rec.mat_ptr.eval(&ray, &rec, direction) / scattered_pdf;
+                ray = rec.spawn_ray(direction, ray.time);
+                bsdf_pdf = Some(scattered_pdf);
+            }
+            None => break,
+        }
+
+        if context
+            .russian_roulette_depth
+            .is_some_and(|depth| bounce + 1 >= depth)
+        {
+            //dividing by the chance of surviving keeps the expected throughput the same
+            let survival = throughput.reduce_partial_max().min(1.0);
+            sampler.start_roulette(bounce);
+            if sampler.get_1d() >= survival {
+                break;
+            }
+            throughput /= survival;
         }
-        return Color::new(0.0, 0.0, 0.0);
     }
-    let unit_direction = unit_vector(r.direction);
-    let t = 0.5 * (unit_direction.y + 1.0);
-    (1.0 - t) * Color::new(1.0, 1.0, 1.0) + t * Color::new(0.5, 0.7, 1.0)
+
+    color
+}
+
+/// Next-event estimation: the light arriving at `rec` from a point picked on one of the lights,
+/// weighted against the chance that scattering would have found it instead.
+fn sample_light<T: Hittable>(
+    context: &PathContext<'_, T>,
+    r_in: &Ray,
+    rec: &HitRecord,
+    bsdf: &dyn Pdf,
+    bounce: usize,
+    sampler: &mut dyn Sampler,
+) -> Color {
+    let black = Color::new(0.0, 0.0, 0.0);
+    if context.lights.objects.is_empty() {
+        return black;
+    }
+
+    sampler.start_light(bounce);
+    let lights = HittablePdf::new(context.lights, rec.p);
+    let direction = lights.generate(sampler);
+    let light_pdf = lights.value(&direction);
+    if light_pdf <= 0.0 {
+        return black;
+    }
+    let f = rec.mat_ptr.eval(r_in, rec, direction);
+    if f.is_approx_zero() {
+        return black;
+    }
+
+    //the shadow ray: whatever it hits first is what this sample of the light sees
+    let shadow_ray = rec.spawn_ray(direction, r_in.time);
+    let light_rec = match context.world.hit(&shadow_ray, 0.001, f64::INFINITY) {
+        Some(light_rec) => light_rec,
+        None => return black,
+    };
+    let radiance = light_rec
+        .mat_ptr
+        .emitted(light_rec.u, light_rec.v, &light_rec.p);
+    let weight = context
+        .mis_heuristic
+        .weight(light_pdf, bsdf.value(&direction));
+    f * radiance * weight / light_pdf
 }
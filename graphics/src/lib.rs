@@ -1,12 +1,29 @@
 pub mod aabb;
+pub mod bvh;
 pub mod camera;
+pub mod constant_medium;
+pub mod export;
+pub mod film;
+pub mod filter;
 pub mod hittable;
 pub mod hittable_list;
 pub mod image;
 pub mod material;
+pub mod microfacet;
+pub mod obj;
+pub mod onb;
+pub mod pdf;
+pub mod perlin;
+pub mod quad;
 pub mod ray;
+pub mod rng;
+pub mod sampler;
 pub mod scenes;
 pub mod sphere;
+pub mod texture;
+pub mod tonemap;
+pub mod transform;
+pub mod triangle;
 mod utils;
 
 use vek::vec::Vec3;
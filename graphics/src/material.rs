@@ -1,69 +1,117 @@
 use crate::hittable::HitRecord;
+use crate::microfacet::{
+    fresnel_conductor, fresnel_dielectric, Microfacet, MicrofacetDistribution,
+};
+use crate::onb::Onb;
+use crate::pdf::{CosinePdf, Pdf, SpherePdf};
 use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::texture::{SolidColor, Texture};
 use crate::utils::*;
 use crate::Color;
-use rand::{thread_rng, Rng};
+use crate::Point3;
+use std::f64::consts::PI;
+use std::sync::Arc;
 use vek::vec::Vec3;
 
 pub trait Material {
-    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatterData>;
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatterData>;
+
+    /// The BSDF times the cosine at the surface (or the phase function, for media) for light
+    /// arriving along `direction` and leaving back along `r_in`. Only materials that scatter
+    /// through a `Pdf` need this, since no other direction could ever pick out a specular ray.
+    fn eval(&self, _r_in: &Ray, _rec: &HitRecord, _direction: Vec3<f64>) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    fn emitted(&self, _u: f64, _v: f64, _p: &Point3) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    /// A shading normal to use in place of `rec.normal`, for materials that add surface detail.
+    /// It is put into the hit record before `scatter` or `eval` see it.
+    fn perturbed_normal(&self, _r_in: &Ray, _rec: &HitRecord) -> Option<Vec3<f64>> {
+        None
+    }
 }
 
 pub struct Lambertian {
-    albedo: Color,
+    albedo: Arc<dyn Texture + Send + Sync>,
 }
 
-pub struct ScatterData {
-    pub scattered_ray: Ray,
-    pub attenuation: Color,
+pub enum ScatterData {
+    /// A ray picked from a delta distribution, like a mirror's. Lights can't usefully be sampled
+    /// from specular hits.
+    Specular { ray: Ray, attenuation: Color },
+    /// The distribution to draw the scattered direction from. Each direction is weighted by
+    /// `Material::eval` over the density it was drawn with.
+    Pdf(Box<dyn Pdf>),
 }
 
 impl Lambertian {
     pub fn new(albedo: Color) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(albedo)))
+    }
+
+    pub fn from_texture(albedo: Arc<dyn Texture + Send + Sync>) -> Self {
         Self { albedo }
     }
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatterData> {
-        let mut scatter_direction = rec.normal + random_unit_vector(); //feel like i should be able to write this without needing it to be mut
-
-        if scatter_direction.is_approx_zero() {
-            scatter_direction = rec.normal;
-        }
+    fn scatter(
+        &self,
+        _r_in: &Ray,
+        rec: &HitRecord,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<ScatterData> {
+        Some(ScatterData::Pdf(Box::new(CosinePdf::new(rec.normal))))
+    }
 
-        Some(ScatterData {
-            scattered_ray: Ray::new(rec.p, scatter_direction, r_in.time),
-            attenuation: self.albedo,
-        })
+    fn eval(&self, _r_in: &Ray, rec: &HitRecord, direction: Vec3<f64>) -> Color {
+        let cosine = Vec3::dot(rec.normal, unit_vector(direction));
+        self.albedo.value(rec.u, rec.v, &rec.p) * cosine.max(0.0) / PI
     }
 }
 
 pub struct Metal {
-    albedo: Color,
+    albedo: Arc<dyn Texture + Send + Sync>,
     fuzz: f64,
 }
 
 impl Metal {
     pub fn new(albedo: Color, fuzz: f64) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(albedo)), fuzz)
+    }
+
+    pub fn from_texture(albedo: Arc<dyn Texture + Send + Sync>, fuzz: f64) -> Self {
         let fuzz = if fuzz < 1.0 { fuzz } else { 1.0 };
         Self { albedo, fuzz }
     }
 }
 
 impl Material for Metal {
-    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatterData> {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatterData> {
         let reflected = unit_vector(r_in.direction).reflected(rec.normal);
-        let scattered = Ray::new(
-            rec.p,
-            reflected + self.fuzz * random_in_unit_sphere(),
+        let scattered = rec.spawn_ray(
+            reflected + self.fuzz * random_in_unit_sphere(sampler),
             r_in.time,
         );
 
         if Vec3::dot(scattered.direction, rec.normal) > 0.0 {
-            Some(ScatterData {
-                scattered_ray: scattered,
-                attenuation: self.albedo,
+            Some(ScatterData::Specular {
+                ray: scattered,
+                attenuation: self.albedo.value(rec.u, rec.v, &rec.p),
             })
         } else {
             None
@@ -85,19 +133,22 @@ impl Dielectric {
     fn reflectance(cosine: f64, ref_idx: f64) -> f64 {
         let r0 = (1.0 - ref_idx) / (1.0 + ref_idx);
         let r0 = r0 * r0;
-        return r0 + (1.0 - r0) * ((1.0 - cosine).powf(5.0));
+        r0 + (1.0 - r0) * ((1.0 - cosine).powf(5.0))
     }
 }
 
 impl Material for Dielectric {
-    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatterData> {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatterData> {
         let refraction_ratio = if rec.front_face {
             1.0 / self.index_of_refraction
         } else {
             self.index_of_refraction
         };
-        let mut rng = thread_rng();
-
         let unit_direction = unit_vector(r_in.direction);
 
         let cos_theta = Vec3::dot(-unit_direction, rec.normal).min(1.0);
@@ -105,17 +156,823 @@ impl Material for Dielectric {
 
         let cannot_refract = refraction_ratio * sin_theta > 1.0;
         //this is true, then the snell's law equation has no solutions and so the ray reflects instead of refracting
-        let direction = if cannot_refract
-            || (Dielectric::reflectance(cos_theta, refraction_ratio) > rng.gen_range(0.0..1.0))
+        //refracted radiance is spread over a wider solid angle on the less dense side, by the
+        //square of the ratio of the indices
+        let (direction, attenuation) = if cannot_refract
+            || (Dielectric::reflectance(cos_theta, refraction_ratio) > sampler.get_1d())
         {
-            unit_direction.reflected(rec.normal)
+            (unit_direction.reflected(rec.normal), 1.0)
         } else {
-            unit_direction.refracted(rec.normal, refraction_ratio)
+            (
+                unit_direction.refracted(rec.normal, refraction_ratio),
+                refraction_ratio * refraction_ratio,
+            )
         };
 
-        Some(ScatterData {
-            attenuation: Color::new(1.0, 1.0, 1.0),
-            scattered_ray: Ray::new(rec.p, direction, r_in.time),
+        Some(ScatterData::Specular {
+            ray: rec.spawn_ray(direction, r_in.time),
+            attenuation: Color::broadcast(attenuation),
         })
     }
 }
+
+/// The complex index of refraction `eta + ik` of a conductor at the red, green and blue primaries.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ComplexIor {
+    pub eta: Color,
+    /// The extinction coefficient, how quickly light dies out inside the metal.
+    pub k: Color,
+}
+
+impl ComplexIor {
+    pub fn new(eta: Color, k: Color) -> Self {
+        Self { eta, k }
+    }
+
+    pub fn gold() -> Self {
+        Self::new(
+            Color::new(0.143, 0.374, 1.442),
+            Color::new(3.983, 2.385, 1.603),
+        )
+    }
+
+    pub fn copper() -> Self {
+        Self::new(
+            Color::new(0.200, 0.924, 1.102),
+            Color::new(3.912, 2.452, 2.142),
+        )
+    }
+
+    pub fn aluminium() -> Self {
+        Self::new(
+            Color::new(1.657, 0.880, 0.521),
+            Color::new(9.224, 6.270, 4.837),
+        )
+    }
+
+    pub fn silver() -> Self {
+        Self::new(
+            Color::new(0.155, 0.117, 0.138),
+            Color::new(4.828, 3.122, 2.147),
+        )
+    }
+}
+
+//a frame around the normal at the hit with x along the surface's u direction, so anisotropic
+//highlights follow the parameterization, and the direction back along the incoming ray in it
+fn shading_frame(r_in: &Ray, rec: &HitRecord) -> (Onb, Vec3<f64>) {
+    let uvw = Onb::from_w_and_u(rec.normal, rec.tangent);
+    let wo = uvw.to_local(-unit_vector(r_in.direction));
+    (uvw, wo)
+}
+
+/// A metal whose roughness comes from a microfacet distribution, in place of `Metal`'s fuzz.
+/// Unlike fuzz it conserves energy up to the light lost between the facets, and its color
+/// comes from the metal's measured index of refraction.
+pub struct RoughConductor {
+    ior: ComplexIor,
+    microfacet: Microfacet,
+}
+
+impl RoughConductor {
+    pub fn new(ior: ComplexIor, distribution: MicrofacetDistribution, roughness: f64) -> Self {
+        Self {
+            ior,
+            microfacet: Microfacet::new(distribution, roughness),
+        }
+    }
+}
+
+impl Material for RoughConductor {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<ScatterData> {
+        let (uvw, wo) = shading_frame(r_in, rec);
+        if self.microfacet.is_smooth() {
+            let direction = unit_vector(r_in.direction).reflected(rec.normal);
+            return Some(ScatterData::Specular {
+                ray: rec.spawn_ray(direction, r_in.time),
+                attenuation: fresnel_conductor(wo.z, self.ior.eta, self.ior.k),
+            });
+        }
+        Some(ScatterData::Pdf(Box::new(MicrofacetReflectionPdf {
+            uvw,
+            wo,
+            microfacet: self.microfacet,
+        })))
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, direction: Vec3<f64>) -> Color {
+        let black = Color::new(0.0, 0.0, 0.0);
+        let (uvw, wo) = shading_frame(r_in, rec);
+        let wi = uvw.to_local(unit_vector(direction));
+        if self.microfacet.is_smooth() || wo.z <= 0.0 || wi.z <= 0.0 {
+            return black;
+        }
+        let wm = unit_vector(wo + wi);
+        let fresnel = fresnel_conductor(Vec3::dot(wi, wm), self.ior.eta, self.ior.k);
+        fresnel * self.microfacet.d(wm) * self.microfacet.g(wo, wi) / (4.0 * wo.z)
+    }
+}
+
+/// Glass with a microfacet rough surface, which both reflects and refracts into a blur. Like
+/// `Dielectric`, it doesn't absorb anything.
+pub struct RoughDielectric {
+    index_of_refraction: f64,
+    microfacet: Microfacet,
+}
+
+impl RoughDielectric {
+    pub fn new(
+        index_of_refraction: f64,
+        distribution: MicrofacetDistribution,
+        roughness: f64,
+    ) -> Self {
+        Self {
+            index_of_refraction,
+            microfacet: Microfacet::new(distribution, roughness),
+        }
+    }
+
+    //the index on the far side of the surface over the one on the side the ray came from
+    fn relative_eta(&self, rec: &HitRecord) -> f64 {
+        if rec.front_face {
+            self.index_of_refraction
+        } else {
+            1.0 / self.index_of_refraction
+        }
+    }
+}
+
+impl Material for RoughDielectric {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatterData> {
+        let (uvw, wo) = shading_frame(r_in, rec);
+        let eta = self.relative_eta(rec);
+        if self.microfacet.is_smooth() {
+            //scaled through the boundary the same way as `Microfacet::transmission`
+            let unit_direction = unit_vector(r_in.direction);
+            let (direction, attenuation) = if fresnel_dielectric(wo.z, eta) > sampler.get_1d() {
+                (unit_direction.reflected(rec.normal), 1.0)
+            } else {
+                (
+                    unit_direction.refracted(rec.normal, 1.0 / eta),
+                    1.0 / (eta * eta),
+                )
+            };
+            return Some(ScatterData::Specular {
+                ray: rec.spawn_ray(direction, r_in.time),
+                attenuation: Color::broadcast(attenuation),
+            });
+        }
+        Some(ScatterData::Pdf(Box::new(RoughDielectricPdf {
+            uvw,
+            wo,
+            eta,
+            microfacet: self.microfacet,
+        })))
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, direction: Vec3<f64>) -> Color {
+        let (uvw, wo) = shading_frame(r_in, rec);
+        let wi = uvw.to_local(unit_vector(direction));
+        if self.microfacet.is_smooth() || wo.z <= 0.0 || wi.z == 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        let eta = self.relative_eta(rec);
+        let m = &self.microfacet;
+        let value = if wi.z > 0.0 {
+            let wm = unit_vector(wo + wi);
+            let fresnel = fresnel_dielectric(Vec3::dot(wo, wm), eta);
+            fresnel * m.d(wm) * m.g(wo, wi) / (4.0 * wo.z)
+        } else {
+            m.transmission(wo, wi, eta)
+        };
+        Color::new(value, value, value)
+    }
+}
+
+//reflections off visible microfacet normals. A reflection that ends up below the surface is
+//lost, which generate reports as a zero vector
+struct MicrofacetReflectionPdf {
+    uvw: Onb,
+    wo: Vec3<f64>,
+    microfacet: Microfacet,
+}
+
+impl Pdf for MicrofacetReflectionPdf {
+    fn value(&self, direction: &Vec3<f64>) -> f64 {
+        if direction.is_approx_zero() {
+            return 0.0;
+        }
+        let wi = self.uvw.to_local(unit_vector(*direction));
+        self.microfacet.reflection_pdf(self.wo, wi)
+    }
+
+    fn generate(&self, sampler: &mut dyn Sampler) -> Vec3<f64> {
+        match self.microfacet.sample_reflection(self.wo, sampler.get_2d()) {
+            Some(wi) => self.uvw.local(wi),
+            None => Vec3::zero(),
+        }
+    }
+}
+
+//picks a visible microfacet normal, then reflects or refracts through it in proportion to the
+//Fresnel reflectance there
+struct RoughDielectricPdf {
+    uvw: Onb,
+    wo: Vec3<f64>,
+    eta: f64,
+    microfacet: Microfacet,
+}
+
+impl Pdf for RoughDielectricPdf {
+    fn value(&self, direction: &Vec3<f64>) -> f64 {
+        if direction.is_approx_zero() {
+            return 0.0;
+        }
+        let wi = self.uvw.to_local(unit_vector(*direction));
+        self.microfacet.dielectric_pdf(self.wo, wi, self.eta)
+    }
+
+    fn generate(&self, sampler: &mut dyn Sampler) -> Vec3<f64> {
+        let u = sampler.get_2d();
+        let u_fresnel = sampler.get_1d();
+        match self
+            .microfacet
+            .sample_dielectric(self.wo, u, u_fresnel, self.eta)
+        {
+            Some(wi) => self.uvw.local(wi),
+            None => Vec3::zero(),
+        }
+    }
+}
+
+/// An input to `Principled`, either the same everywhere or looked up in a texture. Scalar inputs
+/// read the texture's red channel, so a grayscale image with a linear color space works as is.
+#[derive(Clone)]
+pub enum Param<T> {
+    Constant(T),
+    Texture(Arc<dyn Texture + Send + Sync>),
+}
+
+impl Param<f64> {
+    fn value(&self, rec: &HitRecord) -> f64 {
+        match self {
+            Param::Constant(value) => *value,
+            Param::Texture(texture) => texture.value(rec.u, rec.v, &rec.p).x,
+        }
+    }
+}
+
+impl Param<Color> {
+    fn value(&self, rec: &HitRecord) -> Color {
+        match self {
+            Param::Constant(value) => *value,
+            Param::Texture(texture) => texture.value(rec.u, rec.v, &rec.p),
+        }
+    }
+}
+
+impl From<f64> for Param<f64> {
+    fn from(value: f64) -> Self {
+        Param::Constant(value)
+    }
+}
+
+impl From<Color> for Param<Color> {
+    fn from(value: Color) -> Self {
+        Param::Constant(value)
+    }
+}
+
+impl<T> From<Arc<dyn Texture + Send + Sync>> for Param<T> {
+    fn from(texture: Arc<dyn Texture + Send + Sync>) -> Self {
+        Param::Texture(texture)
+    }
+}
+
+/// The inputs to a `Principled` material. The scalars all run from 0 to 1.
+#[derive(Clone)]
+pub struct PrincipledParams {
+    /// The diffuse albedo of a dielectric, the color at normal incidence of a metal, and the
+    /// tint of transmitted light.
+    pub base_color: Param<Color>,
+    /// Blends from a dielectric at 0 to a metal at 1.
+    pub metallic: Param<f64>,
+    /// Anything below 0.05 is treated as 0.05. Use `Metal` or `Dielectric` for a perfect mirror
+    /// or clear glass.
+    pub roughness: Param<f64>,
+    /// Stretches highlights along the shading frame's tangent.
+    pub anisotropic: Param<f64>,
+    /// The strength of a dielectric's reflection. 0.5 is 4% at normal incidence, which is an
+    /// index of refraction of 1.5, and transmitted light refracts to match.
+    pub specular: Param<f64>,
+    /// A soft white rim at grazing angles, as on cloth.
+    pub sheen: Param<f64>,
+    /// A second, colorless specular layer on top, like lacquer.
+    pub clearcoat: Param<f64>,
+    pub clearcoat_roughness: Param<f64>,
+    /// How much of a dielectric lets light through instead of scattering it diffusely.
+    pub transmission: Param<f64>,
+}
+
+impl PrincipledParams {
+    /// A plastic with the given color and medium roughness. Everything else is off.
+    pub fn new(base_color: Color) -> Self {
+        Self {
+            base_color: base_color.into(),
+            metallic: 0.0.into(),
+            roughness: 0.5.into(),
+            anisotropic: 0.0.into(),
+            specular: 0.5.into(),
+            sheen: 0.0.into(),
+            clearcoat: 0.0.into(),
+            clearcoat_roughness: 0.1.into(),
+            transmission: 0.0.into(),
+        }
+    }
+}
+
+/// One material for most surfaces, after Disney's principled BSDF: a diffuse base, a GGX
+/// specular layer that blends from dielectric to metal, rough transmission, sheen and a
+/// clearcoat, each of which can be driven by a texture.
+pub struct Principled {
+    params: PrincipledParams,
+}
+
+const MIN_ROUGHNESS: f64 = 0.05;
+
+impl Principled {
+    pub fn new(params: PrincipledParams) -> Self {
+        Self { params }
+    }
+
+    fn lobes(&self, rec: &HitRecord, wo: Vec3<f64>) -> PrincipledLobes {
+        let p = &self.params;
+        let base_color = p.base_color.value(rec);
+        let metallic = p.metallic.value(rec).clamp(0.0, 1.0);
+        let roughness = p.roughness.value(rec).clamp(MIN_ROUGHNESS, 1.0);
+        let sheen = p.sheen.value(rec).clamp(0.0, 1.0);
+        let clearcoat = p.clearcoat.value(rec).clamp(0.0, 1.0);
+        let transmission = p.transmission.value(rec).clamp(0.0, 1.0);
+
+        //stretch the highlight by up to 10:1 while keeping its area about the same. Where that
+        //takes the long axis past a roughness of 1, both axes are scaled back together so that
+        //rough surfaces keep their stretch instead of having it clamped away
+        let aspect = (1.0 - 0.9 * p.anisotropic.value(rec).clamp(0.0, 1.0)).sqrt();
+        let roughness_x = roughness / aspect.sqrt();
+        let roughness_y = roughness * aspect.sqrt();
+        let overshoot = roughness_x.max(1.0);
+        let specular = Microfacet::anisotropic(
+            MicrofacetDistribution::Ggx,
+            roughness_x / overshoot,
+            roughness_y / overshoot,
+        );
+        let clearcoat_microfacet = Microfacet::new(
+            MicrofacetDistribution::Ggx,
+            p.clearcoat_roughness.value(rec).clamp(MIN_ROUGHNESS, 1.0),
+        );
+
+        //the index of refraction whose reflectance at normal incidence is 0.08 * specular
+        let sqrt_f0 = (0.08 * p.specular.value(rec).clamp(0.0, 1.0)).sqrt();
+        let ior = (1.0 + sqrt_f0) / (1.0 - sqrt_f0);
+        let eta = if rec.front_face { ior } else { 1.0 / ior };
+
+        //pick lobes roughly in proportion to how much light each one sends back from wo
+        let dielectric = 1.0 - metallic;
+        let reflectance = fresnel_dielectric(wo.z, eta);
+        let specular_color = dielectric * reflectance
+            + metallic * schlick(base_color, wo.z).map(|c| c.clamp(0.0, 1.0));
+        let mut weights = [
+            dielectric * (1.0 - transmission) * (luminance(base_color) + sheen),
+            luminance(specular_color),
+            dielectric * transmission * luminance(base_color),
+            clearcoat * fresnel_dielectric(wo.z, CLEARCOAT_IOR),
+        ];
+        let total: f64 = weights.iter().map(|w| w.max(0.0)).sum();
+        if total > 0.0 {
+            weights.iter_mut().for_each(|w| *w = w.max(0.0) / total);
+        } else {
+            weights = [1.0, 0.0, 0.0, 0.0];
+        }
+
+        PrincipledLobes {
+            base_color,
+            metallic,
+            roughness,
+            sheen,
+            clearcoat,
+            transmission,
+            eta,
+            specular,
+            clearcoat_microfacet,
+            weights,
+        }
+    }
+}
+
+impl Material for Principled {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<ScatterData> {
+        let (uvw, wo) = shading_frame(r_in, rec);
+        if wo.z <= 0.0 {
+            return None;
+        }
+        Some(ScatterData::Pdf(Box::new(PrincipledPdf {
+            uvw,
+            wo,
+            lobes: self.lobes(rec, wo),
+        })))
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, direction: Vec3<f64>) -> Color {
+        let (uvw, wo) = shading_frame(r_in, rec);
+        if wo.z <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        let wi = uvw.to_local(unit_vector(direction));
+        self.lobes(rec, wo).eval(wo, wi)
+    }
+}
+
+const CLEARCOAT_IOR: f64 = 1.5;
+
+fn schlick(f0: Color, cosine: f64) -> Color {
+    f0 + (Color::one() - f0) * (1.0 - cosine.clamp(0.0, 1.0)).powi(5)
+}
+
+//a principled material's inputs at one hit, in the shading frame
+#[derive(Clone, Copy)]
+struct PrincipledLobes {
+    base_color: Color,
+    metallic: f64,
+    roughness: f64,
+    sheen: f64,
+    clearcoat: f64,
+    transmission: f64,
+    eta: f64,
+    specular: Microfacet,
+    clearcoat_microfacet: Microfacet,
+    //the chance of sampling the diffuse, specular, transmission and clearcoat lobes. The
+    //transmission lobe is sampled like a rough dielectric, which reflects as well as refracts,
+    //so that light still gets through wherever total internal reflection stops the macro surface
+    weights: [f64; 4],
+}
+
+impl PrincipledLobes {
+    fn eval(&self, wo: Vec3<f64>, wi: Vec3<f64>) -> Color {
+        let dielectric = 1.0 - self.metallic;
+        if wi.z < 0.0 {
+            let transmission = dielectric * self.transmission;
+            if transmission <= 0.0 {
+                return Color::zero();
+            }
+            return self.base_color * transmission * self.specular.transmission(wo, wi, self.eta);
+        }
+        if wi.z == 0.0 {
+            return Color::zero();
+        }
+
+        let wh = unit_vector(wo + wi);
+        let cos_d = Vec3::dot(wi, wh);
+
+        //Burley's diffuse, which darkens smooth surfaces and brightens rough ones at grazing angles
+        let fd90 = 0.5 + 2.0 * self.roughness * cos_d * cos_d;
+        let fd = (1.0 + (fd90 - 1.0) * (1.0 - wi.z).powi(5))
+            * (1.0 + (fd90 - 1.0) * (1.0 - wo.z).powi(5));
+        let sheen = self.sheen * (1.0 - cos_d).powi(5);
+        let diffuse = dielectric
+            * (1.0 - self.transmission)
+            * (self.base_color * fd / PI + Color::broadcast(sheen))
+            * wi.z;
+
+        let fresnel = dielectric * fresnel_dielectric(Vec3::dot(wo, wh), self.eta)
+            + self.metallic * schlick(self.base_color, Vec3::dot(wo, wh));
+        let specular = fresnel * self.specular.d(wh) * self.specular.g(wo, wi) / (4.0 * wo.z);
+
+        let clearcoat = self.clearcoat
+            * fresnel_dielectric(Vec3::dot(wo, wh), CLEARCOAT_IOR)
+            * self.clearcoat_microfacet.d(wh)
+            * self.clearcoat_microfacet.g(wo, wi)
+            / (4.0 * wo.z);
+
+        diffuse + specular + Color::broadcast(clearcoat)
+    }
+
+    fn pdf(&self, wo: Vec3<f64>, wi: Vec3<f64>) -> f64 {
+        let [diffuse, specular, transmission, clearcoat] = self.weights;
+        let mut pdf = 0.0;
+        if transmission > 0.0 {
+            pdf += transmission * self.specular.dielectric_pdf(wo, wi, self.eta);
+        }
+        if wi.z > 0.0 {
+            pdf += diffuse * wi.z / PI
+                + specular * self.specular.reflection_pdf(wo, wi)
+                + clearcoat * self.clearcoat_microfacet.reflection_pdf(wo, wi);
+        }
+        pdf
+    }
+
+    fn sample(&self, wo: Vec3<f64>, sampler: &mut dyn Sampler) -> Option<Vec3<f64>> {
+        let [diffuse, specular, transmission, _] = self.weights;
+        let u = sampler.get_1d();
+        if u < diffuse {
+            let disk = random_in_unit_disk(sampler);
+            let z = (1.0 - disk.x * disk.x - disk.y * disk.y).max(0.0).sqrt();
+            Some(Vec3::new(disk.x, disk.y, z))
+        } else if u < diffuse + specular {
+            self.specular.sample_reflection(wo, sampler.get_2d())
+        } else if u < diffuse + specular + transmission {
+            let u = sampler.get_2d();
+            self.specular
+                .sample_dielectric(wo, u, sampler.get_1d(), self.eta)
+        } else {
+            self.clearcoat_microfacet
+                .sample_reflection(wo, sampler.get_2d())
+        }
+    }
+}
+
+//picks one of the lobes, so the density is the weighted sum of theirs. Lost samples come back as
+//a zero vector
+struct PrincipledPdf {
+    uvw: Onb,
+    wo: Vec3<f64>,
+    lobes: PrincipledLobes,
+}
+
+impl Pdf for PrincipledPdf {
+    fn value(&self, direction: &Vec3<f64>) -> f64 {
+        if direction.is_approx_zero() {
+            return 0.0;
+        }
+        let wi = self.uvw.to_local(unit_vector(*direction));
+        self.lobes.pdf(self.wo, wi)
+    }
+
+    fn generate(&self, sampler: &mut dyn Sampler) -> Vec3<f64> {
+        match self.lobes.sample(self.wo, sampler) {
+            Some(wi) => self.uvw.local(wi),
+            None => Vec3::zero(),
+        }
+    }
+}
+
+//how far u and v step for the finite differences of a bump map, before scaling down for surfaces
+//that stretch far per unit of u or v
+const BUMP_DELTA: f64 = 0.0005;
+
+enum SurfaceDetail {
+    NormalMap(Arc<dyn Texture + Send + Sync>),
+    Bump {
+        height: Arc<dyn Texture + Send + Sync>,
+        scale: f64,
+    },
+}
+
+/// Shades another material as if its surface were bent by a normal or bump map, without adding
+/// any geometry. Works on any surface whose hits carry tangents.
+pub struct NormalMapped {
+    material: Arc<dyn Material + Send + Sync>,
+    detail: SurfaceDetail,
+}
+
+impl NormalMapped {
+    /// Takes normals from a tangent-space normal map, whose red runs the way u increases, green
+    /// the way v increases and blue out of the surface, each mapped from [0, 1] to [-1, 1].
+    /// Image maps should be opened with `ColorSpace::Linear`.
+    pub fn normal_map(
+        material: Arc<dyn Material + Send + Sync>,
+        map: Arc<dyn Texture + Send + Sync>,
+    ) -> Self {
+        Self {
+            material,
+            detail: SurfaceDetail::NormalMap(map),
+        }
+    }
+
+    /// Bumps the surface up by `scale` world units per unit of the height texture's red channel.
+    pub fn bump(
+        material: Arc<dyn Material + Send + Sync>,
+        height: Arc<dyn Texture + Send + Sync>,
+        scale: f64,
+    ) -> Self {
+        Self {
+            material,
+            detail: SurfaceDetail::Bump { height, scale },
+        }
+    }
+}
+
+impl Material for NormalMapped {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatterData> {
+        self.material.scatter(r_in, rec, sampler)
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, direction: Vec3<f64>) -> Color {
+        self.material.eval(r_in, rec, direction)
+    }
+
+    fn emitted(&self, u: f64, v: f64, p: &Point3) -> Color {
+        self.material.emitted(u, v, p)
+    }
+
+    fn perturbed_normal(&self, r_in: &Ray, rec: &HitRecord) -> Option<Vec3<f64>> {
+        //build on any detail the wrapped material adds itself
+        let n = self
+            .material
+            .perturbed_normal(r_in, rec)
+            .unwrap_or(rec.normal);
+
+        let perturbed = match &self.detail {
+            SurfaceDetail::NormalMap(map) => {
+                //a frame around the normal with its axes lined up with the texture's
+                let uvw = Onb::from_w_and_u(n, rec.tangent);
+                let t = uvw.u;
+                let b = if Vec3::dot(uvw.v, rec.bitangent) < 0.0 {
+                    -uvw.v
+                } else {
+                    uvw.v
+                };
+                let c = 2.0 * map.value(rec.u, rec.v, &rec.p) - Color::one();
+                c.x * t + c.y * b + c.z * n
+            }
+            SurfaceDetail::Bump { height, scale } => {
+                let h = |u: f64, v: f64, p: Point3| scale * height.value(u, v, &p).x;
+                let du = BUMP_DELTA / rec.tangent.magnitude().max(1.0);
+                let dv = BUMP_DELTA / rec.bitangent.magnitude().max(1.0);
+                let h0 = h(rec.u, rec.v, rec.p);
+                let dhdu = (h(rec.u + du, rec.v, rec.p + du * rec.tangent) - h0) / du;
+                let dhdv = (h(rec.u, rec.v + dv, rec.p + dv * rec.bitangent) - h0) / dv;
+
+                //the tangents of the displaced surface, ignoring how the normal itself turns
+                let dpdu = rec.tangent + dhdu * n;
+                let dpdv = rec.bitangent + dhdv * n;
+                let m = Vec3::cross(dpdu, dpdv);
+                if Vec3::dot(m, n) < 0.0 {
+                    -m
+                } else {
+                    m
+                }
+            }
+        };
+
+        //a normal bent past the viewer would shade a side of the surface that can't be seen
+        if perturbed.magnitude_squared() < 1e-24 || Vec3::dot(perturbed, r_in.direction) >= 0.0 {
+            None
+        } else {
+            Some(unit_vector(perturbed))
+        }
+    }
+}
+
+pub struct DiffuseLight {
+    emit: Arc<dyn Texture + Send + Sync>,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Color) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(emit)))
+    }
+
+    pub fn from_texture(emit: Arc<dyn Texture + Send + Sync>) -> Self {
+        Self { emit }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(
+        &self,
+        _r_in: &Ray,
+        _rec: &HitRecord,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<ScatterData> {
+        None
+    }
+
+    fn emitted(&self, u: f64, v: f64, p: &Point3) -> Color {
+        self.emit.value(u, v, p)
+    }
+}
+
+/// Scatters light equally in every direction, for use as the phase function of a medium.
+pub struct Isotropic {
+    albedo: Arc<dyn Texture + Send + Sync>,
+}
+
+impl Isotropic {
+    pub fn new(albedo: Color) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(albedo)))
+    }
+
+    pub fn from_texture(albedo: Arc<dyn Texture + Send + Sync>) -> Self {
+        Self { albedo }
+    }
+}
+
+impl Material for Isotropic {
+    fn scatter(
+        &self,
+        _r_in: &Ray,
+        _rec: &HitRecord,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<ScatterData> {
+        Some(ScatterData::Pdf(Box::new(SpherePdf)))
+    }
+
+    fn eval(&self, _r_in: &Ray, rec: &HitRecord, _direction: Vec3<f64>) -> Color {
+        self.albedo.value(rec.u, rec.v, &rec.p) / (4.0 * PI)
+    }
+}
+
+/// The Henyey-Greenstein phase function. Positive `g` favours forward scattering, negative `g`
+/// favours back scattering and zero is isotropic.
+pub struct HenyeyGreenstein {
+    albedo: Arc<dyn Texture + Send + Sync>,
+    g: f64,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(albedo: Color, g: f64) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(albedo)), g)
+    }
+
+    /// `g` is clamped to (-1, 1), where the distribution stays finite.
+    pub fn from_texture(albedo: Arc<dyn Texture + Send + Sync>, g: f64) -> Self {
+        Self {
+            albedo,
+            g: g.clamp(-0.999, 0.999),
+        }
+    }
+}
+
+impl Material for HenyeyGreenstein {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        _rec: &HitRecord,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<ScatterData> {
+        Some(ScatterData::Pdf(Box::new(HenyeyGreensteinPdf {
+            uvw: Onb::from_w(r_in.direction),
+            g: self.g,
+        })))
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, direction: Vec3<f64>) -> Color {
+        let cos_theta = Vec3::dot(unit_vector(r_in.direction), unit_vector(direction));
+        self.albedo.value(rec.u, rec.v, &rec.p) * henyey_greenstein(cos_theta, self.g)
+    }
+}
+
+//directions tilted away from the incoming direction, w, by the phase function's angle
+struct HenyeyGreensteinPdf {
+    uvw: Onb,
+    g: f64,
+}
+
+impl HenyeyGreensteinPdf {
+    fn sample_cos_theta(&self, xi: f64) -> f64 {
+        let g = self.g;
+        if g.abs() < 1e-3 {
+            return 1.0 - 2.0 * xi;
+        }
+        let sq = (1.0 - g * g) / (1.0 - g + 2.0 * g * xi);
+        ((1.0 + g * g - sq * sq) / (2.0 * g)).clamp(-1.0, 1.0)
+    }
+}
+
+impl Pdf for HenyeyGreensteinPdf {
+    fn value(&self, direction: &Vec3<f64>) -> f64 {
+        henyey_greenstein(Vec3::dot(self.uvw.w, unit_vector(*direction)), self.g)
+    }
+
+    fn generate(&self, sampler: &mut dyn Sampler) -> Vec3<f64> {
+        let xi = sampler.get_2d();
+        let cos_theta = self.sample_cos_theta(xi.x);
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        self.uvw.spherical(cos_theta, sin_theta, 2.0 * PI * xi.y)
+    }
+}
+
+fn henyey_greenstein(cos_theta: f64, g: f64) -> f64 {
+    let denom = 1.0 + g * g - 2.0 * g * cos_theta;
+    (1.0 - g * g) / (4.0 * PI * denom * denom.sqrt())
+}
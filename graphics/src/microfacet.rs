@@ -0,0 +1,416 @@
+use crate::utils::unit_vector;
+use crate::Color;
+use std::f64::consts::PI;
+use vek::vec::{Vec2, Vec3};
+
+//everything here works in a shading frame where the surface normal is +z
+
+/// The shape of the distribution of microfacet normals on a rough surface.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MicrofacetDistribution {
+    /// Trowbridge-Reitz. Its long tail gives highlights a soft glow around them.
+    #[default]
+    Ggx,
+    /// Gaussian-distributed slopes, which fall off faster than GGX away from the highlight.
+    Beckmann,
+}
+
+/// A rough surface, described by how its microfacet normals are spread around the real one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Microfacet {
+    distribution: MicrofacetDistribution,
+    alpha_x: f64,
+    alpha_y: f64,
+}
+
+//below this alpha the distribution is too spiky to evaluate or sample reliably
+const SMOOTH_ALPHA: f64 = 1e-3;
+
+impl Microfacet {
+    /// `roughness` runs from 0, a perfect mirror, to 1. The distribution's alpha is its square,
+    /// which makes the change in appearance roughly even across the range.
+    pub fn new(distribution: MicrofacetDistribution, roughness: f64) -> Self {
+        Self::anisotropic(distribution, roughness, roughness)
+    }
+
+    /// Roughness that differs along the shading frame's x and y axes.
+    pub fn anisotropic(
+        distribution: MicrofacetDistribution,
+        roughness_x: f64,
+        roughness_y: f64,
+    ) -> Self {
+        Self {
+            distribution,
+            alpha_x: roughness_x.clamp(0.0, 1.0).powi(2),
+            alpha_y: roughness_y.clamp(0.0, 1.0).powi(2),
+        }
+    }
+
+    /// Whether the surface is close enough to smooth that it should be treated as a mirror.
+    pub fn is_smooth(&self) -> bool {
+        self.alpha_x.max(self.alpha_y) < SMOOTH_ALPHA
+    }
+
+    /// The density of microfacets with normal `wm`, per unit solid angle of the macro surface.
+    pub fn d(&self, wm: Vec3<f64>) -> f64 {
+        if wm.z <= 0.0 {
+            return 0.0;
+        }
+        let cos2_theta = wm.z * wm.z;
+        //tan^2 theta, stretched by the roughness along each axis
+        let e = (wm.x * wm.x / (self.alpha_x * self.alpha_x)
+            + wm.y * wm.y / (self.alpha_y * self.alpha_y))
+            / cos2_theta;
+        let normalization = PI * self.alpha_x * self.alpha_y * cos2_theta * cos2_theta;
+        match self.distribution {
+            MicrofacetDistribution::Ggx => 1.0 / (normalization * (1.0 + e) * (1.0 + e)),
+            MicrofacetDistribution::Beckmann => (-e).exp() / normalization,
+        }
+    }
+
+    /// Smith's auxiliary function: how much microfacet area is hidden, seen from `w`.
+    pub fn lambda(&self, w: Vec3<f64>) -> f64 {
+        if w.z == 0.0 {
+            return 0.0;
+        }
+        let alpha2_tan2 = (self.alpha_x * self.alpha_x * w.x * w.x
+            + self.alpha_y * self.alpha_y * w.y * w.y)
+            / (w.z * w.z);
+        match self.distribution {
+            MicrofacetDistribution::Ggx => ((1.0 + alpha2_tan2).sqrt() - 1.0) / 2.0,
+            MicrofacetDistribution::Beckmann => {
+                if alpha2_tan2 == 0.0 {
+                    return 0.0;
+                }
+                //Walter et al.'s rational fit
+                let a = 1.0 / alpha2_tan2.sqrt();
+                if a >= 1.6 {
+                    0.0
+                } else {
+                    (1.0 - 1.259 * a + 0.396 * a * a) / (3.535 * a + 2.181 * a * a)
+                }
+            }
+        }
+    }
+
+    /// The fraction of microfacets visible from `w`.
+    pub fn g1(&self, w: Vec3<f64>) -> f64 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    /// The fraction of microfacets visible from both `wo` and `wi`, with the height correlation
+    /// between the two taken into account.
+    pub fn g(&self, wo: Vec3<f64>, wi: Vec3<f64>) -> f64 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// The density of the normals visible from `wo`, which is what `sample_visible_normal` draws.
+    pub fn visible_d(&self, wo: Vec3<f64>, wm: Vec3<f64>) -> f64 {
+        self.g1(wo) / wo.z.abs() * self.d(wm) * Vec3::dot(wo, wm).abs()
+    }
+
+    /// A microfacet normal drawn in proportion to how much of it is visible from `wo`, so that no
+    /// samples are wasted on facets facing away.
+    pub fn sample_visible_normal(&self, wo: Vec3<f64>, u: Vec2<f64>) -> Vec3<f64> {
+        //both samplers work from above the surface
+        let flip = wo.z < 0.0;
+        let wo = if flip { -wo } else { wo };
+        let wm = match self.distribution {
+            MicrofacetDistribution::Ggx => self.sample_ggx(wo, u),
+            MicrofacetDistribution::Beckmann => self.sample_beckmann(wo, u),
+        };
+        if flip {
+            -wm
+        } else {
+            wm
+        }
+    }
+
+    /// The density with which `sample_reflection` picks `wi`, for `wo` above the surface.
+    pub fn reflection_pdf(&self, wo: Vec3<f64>, wi: Vec3<f64>) -> f64 {
+        if wi.z <= 0.0 {
+            return 0.0;
+        }
+        let wm = unit_vector(wo + wi);
+        self.visible_d(wo, wm) / (4.0 * Vec3::dot(wo, wm).abs())
+    }
+
+    /// `wo` reflected off a visible microfacet normal, or `None` if that sends it below the
+    /// surface.
+    pub fn sample_reflection(&self, wo: Vec3<f64>, u: Vec2<f64>) -> Option<Vec3<f64>> {
+        let wm = self.sample_visible_normal(wo, u);
+        let wi = (-wo).reflected(wm);
+        if wi.z > 0.0 {
+            Some(wi)
+        } else {
+            None
+        }
+    }
+
+    /// The density with which `sample_transmission` picks `wi`, for `wo` above the surface.
+    pub fn transmission_pdf(&self, wo: Vec3<f64>, wi: Vec3<f64>, eta: f64) -> f64 {
+        match refraction_half_vector(wo, wi, eta) {
+            Some(wm) => {
+                let denom = (Vec3::dot(wi, wm) + Vec3::dot(wo, wm) / eta).powi(2);
+                self.visible_d(wo, wm) * Vec3::dot(wi, wm).abs() / denom
+            }
+            None => 0.0,
+        }
+    }
+
+    /// The density with which `sample_dielectric` picks `wi`.
+    pub fn dielectric_pdf(&self, wo: Vec3<f64>, wi: Vec3<f64>, eta: f64) -> f64 {
+        if wi.z > 0.0 {
+            let wm = unit_vector(wo + wi);
+            fresnel_dielectric(Vec3::dot(wo, wm), eta) * self.reflection_pdf(wo, wi)
+        } else if wi.z < 0.0 {
+            match refraction_half_vector(wo, wi, eta) {
+                Some(wm) => {
+                    let reflectance = fresnel_dielectric(Vec3::dot(wo, wm), eta);
+                    (1.0 - reflectance) * self.transmission_pdf(wo, wi, eta)
+                }
+                None => 0.0,
+            }
+        } else {
+            0.0
+        }
+    }
+
+    /// `wo` reflected off or refracted through a visible microfacet normal into a medium `eta`
+    /// times as dense, choosing between the two with `u_fresnel` in proportion to the Fresnel
+    /// reflectance of the microfacet. `None` if the result ends up on the wrong side.
+    pub fn sample_dielectric(
+        &self,
+        wo: Vec3<f64>,
+        u: Vec2<f64>,
+        u_fresnel: f64,
+        eta: f64,
+    ) -> Option<Vec3<f64>> {
+        let wm = self.sample_visible_normal(wo, u);
+        if fresnel_dielectric(Vec3::dot(wo, wm), eta) > u_fresnel {
+            let wi = (-wo).reflected(wm);
+            if wi.z > 0.0 {
+                return Some(wi);
+            }
+        } else {
+            let wi = (-wo).refracted(wm, 1.0 / eta);
+            if wi.z < 0.0 {
+                return Some(wi);
+            }
+        }
+        None
+    }
+
+    /// The BSDF times the cosine for light refracting from `wi` below the surface to `wo` above
+    /// it, less what the microfacets reflect. `eta` is the index below over the index above.
+    pub fn transmission(&self, wo: Vec3<f64>, wi: Vec3<f64>, eta: f64) -> f64 {
+        match refraction_half_vector(wo, wi, eta) {
+            Some(wm) => {
+                let fresnel = fresnel_dielectric(Vec3::dot(wo, wm), eta);
+                let denom = (Vec3::dot(wi, wm) + Vec3::dot(wo, wm) / eta).powi(2);
+                //radiance is compressed into a smaller solid angle going into the denser medium
+                (1.0 - fresnel)
+                    * self.d(wm)
+                    * self.g(wo, wi)
+                    * (Vec3::dot(wi, wm) * Vec3::dot(wo, wm)).abs()
+                    / (wo.z * denom * eta * eta)
+            }
+            None => 0.0,
+        }
+    }
+
+    //Heitz's 2018 method: stretch the view to make the surface isotropic with unit roughness,
+    //where the visible normals project to a disk split into two easily sampled halves
+    fn sample_ggx(&self, wo: Vec3<f64>, u: Vec2<f64>) -> Vec3<f64> {
+        let wh = unit_vector(Vec3::new(self.alpha_x * wo.x, self.alpha_y * wo.y, wo.z));
+        let len2 = wh.x * wh.x + wh.y * wh.y;
+        let t1 = if len2 > 0.0 {
+            Vec3::new(-wh.y, wh.x, 0.0) / len2.sqrt()
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let t2 = Vec3::cross(wh, t1);
+
+        let r = u.x.sqrt();
+        let phi = 2.0 * PI * u.y;
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1.0 + wh.z);
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).max(0.0).sqrt() + s * r * phi.sin();
+        let nh = p1 * t1 + p2 * t2 + (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt() * wh;
+
+        unit_vector(Vec3::new(
+            self.alpha_x * nh.x,
+            self.alpha_y * nh.y,
+            nh.z.max(1e-6),
+        ))
+    }
+
+    //Jakob's method from PBRT: stretch the view the same way, then sample the slopes of the
+    //visible normals and unstretch them
+    fn sample_beckmann(&self, wo: Vec3<f64>, u: Vec2<f64>) -> Vec3<f64> {
+        let wi = unit_vector(Vec3::new(self.alpha_x * wo.x, self.alpha_y * wo.y, wo.z));
+        let (slope_x, slope_y) = beckmann_sample_slopes(wi.z, u);
+
+        let sin_theta = (1.0 - wi.z * wi.z).max(0.0).sqrt();
+        let (cos_phi, sin_phi) = if sin_theta > 0.0 {
+            (wi.x / sin_theta, wi.y / sin_theta)
+        } else {
+            (1.0, 0.0)
+        };
+        let rotated_x = cos_phi * slope_x - sin_phi * slope_y;
+        let rotated_y = sin_phi * slope_x + cos_phi * slope_y;
+
+        unit_vector(Vec3::new(
+            -self.alpha_x * rotated_x,
+            -self.alpha_y * rotated_y,
+            1.0,
+        ))
+    }
+}
+
+/// The microfacet normal that refracts `wo` into `wi`, if there is one facing `wo`.
+pub fn refraction_half_vector(wo: Vec3<f64>, wi: Vec3<f64>, eta: f64) -> Option<Vec3<f64>> {
+    let wm = wi * eta + wo;
+    if wm.is_approx_zero() {
+        return None;
+    }
+    let wm = unit_vector(wm);
+    let wm = if wm.z < 0.0 { -wm } else { wm };
+    if Vec3::dot(wm, wi) >= 0.0 || Vec3::dot(wm, wo) <= 0.0 {
+        return None;
+    }
+    Some(wm)
+}
+
+//the slopes of a visible normal for alpha = 1, seen from cos_theta off the normal
+fn beckmann_sample_slopes(cos_theta: f64, u: Vec2<f64>) -> (f64, f64) {
+    if cos_theta > 0.9999 {
+        let r = (-(1.0 - u.x).ln()).sqrt();
+        let phi = 2.0 * PI * u.y;
+        return (r * phi.cos(), r * phi.sin());
+    }
+
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let tan_theta = sin_theta / cos_theta;
+    let cot_theta = 1.0 / tan_theta;
+    let inv_sqrt_pi = 1.0 / PI.sqrt();
+
+    //invert the cdf of the x slope by Newton's method, bracketed by bisection
+    let mut a = -1.0;
+    let mut c = erf(cot_theta);
+    let sample_x = u.x.max(1e-6);
+    let theta = cos_theta.acos();
+    let fit = 1.0 + theta * (-0.876 + theta * (0.4265 - 0.0594 * theta));
+    let mut b = c - (1.0 + c) * (1.0 - sample_x).powf(fit);
+    let normalization = 1.0 / (1.0 + c + inv_sqrt_pi * tan_theta * (-cot_theta * cot_theta).exp());
+    for _ in 0..9 {
+        if !(b >= a && b <= c) {
+            b = 0.5 * (a + c);
+        }
+        let inv_erf = erf_inv(b);
+        let value = normalization
+            * (1.0 + b + inv_sqrt_pi * tan_theta * (-inv_erf * inv_erf).exp())
+            - sample_x;
+        if value.abs() < 1e-5 {
+            break;
+        }
+        if value > 0.0 {
+            c = b;
+        } else {
+            a = b;
+        }
+        let derivative = normalization * (1.0 - inv_erf * tan_theta);
+        b -= value / derivative;
+    }
+
+    (erf_inv(b), erf_inv(2.0 * u.y.max(1e-6) - 1.0))
+}
+
+//Abramowitz and Stegun 7.1.26, good to about 1e-7
+fn erf(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.3275911 * x.abs());
+    let poly = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let y = 1.0 - poly * (-x * x).exp();
+    y.copysign(x)
+}
+
+//Giles' approximation
+fn erf_inv(x: f64) -> f64 {
+    let x = x.clamp(-0.99999, 0.99999);
+    let mut w = -((1.0 - x) * (1.0 + x)).ln();
+    let p = if w < 5.0 {
+        w -= 2.5;
+        let mut p = 2.810_226_36e-08;
+        p = 3.432_739_39e-07 + p * w;
+        p = -3.523_387_7e-06 + p * w;
+        p = -4.391_506_54e-06 + p * w;
+        p = 0.000_218_580_87 + p * w;
+        p = -0.001_253_725_03 + p * w;
+        p = -0.004_177_681_64 + p * w;
+        p = 0.246_640_727 + p * w;
+        1.501_409_41 + p * w
+    } else {
+        w = w.sqrt() - 3.0;
+        let mut p = -0.000_200_214_257;
+        p = 0.000_100_950_558 + p * w;
+        p = 0.001_349_343_22 + p * w;
+        p = -0.003_673_428_44 + p * w;
+        p = 0.005_739_507_73 + p * w;
+        p = -0.007_622_461_3 + p * w;
+        p = 0.009_438_870_47 + p * w;
+        p = 1.001_674_06 + p * w;
+        2.832_976_82 + p * w
+    };
+    p * x
+}
+
+/// The fraction of light reflected at the boundary of a dielectric, for light arriving `cos_i`
+/// off the normal. `eta` is the index on the far side over the index on the near side.
+pub fn fresnel_dielectric(cos_i: f64, eta: f64) -> f64 {
+    let cos_i = cos_i.clamp(-1.0, 1.0);
+    let (cos_i, eta) = if cos_i < 0.0 {
+        (-cos_i, 1.0 / eta)
+    } else {
+        (cos_i, eta)
+    };
+    let sin2_t = (1.0 - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1.0 {
+        //total internal reflection
+        return 1.0;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    let r_parallel = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let r_perpendicular = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    (r_parallel * r_parallel + r_perpendicular * r_perpendicular) / 2.0
+}
+
+/// The fraction of light reflected by a conductor with complex index of refraction `eta + ik`,
+/// for light arriving `cos_i` off the normal.
+pub fn fresnel_conductor(cos_i: f64, eta: Color, k: Color) -> Color {
+    let cos_i = cos_i.clamp(0.0, 1.0);
+    Color::new(
+        fresnel_conductor_channel(cos_i, eta.x, k.x),
+        fresnel_conductor_channel(cos_i, eta.y, k.y),
+        fresnel_conductor_channel(cos_i, eta.z, k.z),
+    )
+}
+
+fn fresnel_conductor_channel(cos_i: f64, eta: f64, k: f64) -> f64 {
+    let cos2 = cos_i * cos_i;
+    let sin2 = 1.0 - cos2;
+    let t0 = eta * eta - k * k - sin2;
+    let a2_plus_b2 = (t0 * t0 + 4.0 * eta * eta * k * k).sqrt();
+    let a = (0.5 * (a2_plus_b2 + t0)).max(0.0).sqrt();
+
+    let t1 = a2_plus_b2 + cos2;
+    let t2 = 2.0 * cos_i * a;
+    let r_s = (t1 - t2) / (t1 + t2);
+
+    let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+    let t4 = t2 * sin2;
+    let r_p = r_s * (t3 - t4) / (t3 + t4);
+
+    (r_s + r_p) / 2.0
+}
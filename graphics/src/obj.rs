@@ -0,0 +1,512 @@
+use crate::aabb::AABB;
+use crate::bvh::{BvhError, BvhNode, SplitMethod};
+use crate::hittable::{HitRecord, Hittable};
+use crate::hittable_list::HittableList;
+use crate::material::{Dielectric, DiffuseLight, Lambertian, Material, Metal};
+use crate::ray::Ray;
+use crate::triangle::Triangle;
+use crate::Color;
+use crate::Point3;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use vek::vec::{Vec2, Vec3};
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io(PathBuf, io::Error),
+    Parse {
+        path: PathBuf,
+        line: usize,
+        kind: ParseErrorKind,
+    },
+    NoGeometry(PathBuf),
+    Bvh(PathBuf, BvhError),
+}
+
+#[derive(Debug)]
+pub enum ParseErrorKind {
+    InvalidNumber(String),
+    MissingArguments(&'static str),
+    InvalidIndex(String),
+    IndexOutOfRange(i64),
+    TooFewFaceVertices(usize),
+    MaterialBeforeNewmtl,
+    UnknownMaterial(String),
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidNumber(s) => write!(f, "invalid number `{}`", s),
+            Self::MissingArguments(statement) => {
+                write!(f, "not enough arguments for `{}`", statement)
+            }
+            Self::InvalidIndex(s) => write!(f, "invalid face index `{}`", s),
+            Self::IndexOutOfRange(i) => write!(f, "face index {} is out of range", i),
+            Self::TooFewFaceVertices(n) => {
+                write!(f, "face has {} vertices, at least 3 are needed", n)
+            }
+            Self::MaterialBeforeNewmtl => write!(f, "material property set before `newmtl`"),
+            Self::UnknownMaterial(name) => write!(f, "material `{}` was never defined", name),
+        }
+    }
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(path, e) => write!(f, "could not read {}: {}", path.display(), e),
+            Self::Parse { path, line, kind } => {
+                write!(f, "{}:{}: {}", path.display(), line, kind)
+            }
+            Self::NoGeometry(path) => write!(f, "{} contains no faces", path.display()),
+            Self::Bvh(path, e) => write!(f, "could not build a BVH for {}: {}", path.display(), e),
+        }
+    }
+}
+
+impl Error for ObjError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(_, e) => Some(e),
+            Self::Bvh(_, e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// The raw values of one `newmtl` block in an MTL file.
+#[derive(Clone, Debug)]
+pub struct MtlMaterial {
+    pub name: String,
+    pub kd: Color,
+    pub ks: Color,
+    pub ke: Color,
+    pub ns: f64,
+    pub ni: f64,
+    pub d: f64,
+    pub illum: Option<u32>,
+}
+
+impl MtlMaterial {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            kd: Color::new(0.8, 0.8, 0.8),
+            ks: Color::zero(),
+            ke: Color::zero(),
+            ns: 0.0,
+            ni: 1.0,
+            d: 1.0,
+            illum: None,
+        }
+    }
+
+    /// Picks the closest material the renderer has for these MTL values.
+    ///
+    /// Materials with a nonzero `Ke` become `DiffuseLight`, transparent materials (`d < 1` or
+    /// a refractive `illum`) become `Dielectric` with index `Ni`, materials whose specular
+    /// color outweighs their diffuse color become `Metal` with a fuzz derived from `Ns`, and
+    /// everything else is `Lambertian` with `Kd`.
+    pub fn to_material(&self) -> Arc<dyn Material + Send + Sync> {
+        let refractive_illum = matches!(self.illum, Some(4) | Some(6) | Some(7) | Some(9));
+        if self.ke.reduce_partial_max() > 0.0 {
+            Arc::new(DiffuseLight::new(self.ke))
+        } else if self.d < 1.0 || refractive_illum {
+            Arc::new(Dielectric::new(self.ni))
+        } else if self.ks.reduce_partial_max() > self.kd.reduce_partial_max() {
+            let fuzz = (2.0 / (self.ns + 2.0)).sqrt();
+            Arc::new(Metal::new(self.ks, fuzz))
+        } else {
+            Arc::new(Lambertian::new(self.kd))
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct MeshGroup {
+    pub name: String,
+    pub triangles: HittableList,
+}
+
+/// A triangulated OBJ model. Hitting the mesh goes through a BVH built over every group.
+#[derive(Clone)]
+pub struct Mesh {
+    pub groups: Vec<MeshGroup>,
+    bvh: BvhNode,
+}
+
+impl Mesh {
+    /// Loads an OBJ file along with any MTL files it references through `mtllib`.
+    ///
+    /// Faces without a `usemtl` use `default_material`.
+    pub fn load(
+        path: impl AsRef<Path>,
+        default_material: Arc<dyn Material + Send + Sync>,
+    ) -> Result<Self, ObjError> {
+        let path = path.as_ref();
+        let source = read(path)?;
+        let mut parser = ObjParser::new(path, default_material);
+        for (i, line) in source.lines().enumerate() {
+            parser.parse_line(line, i + 1)?;
+        }
+        parser.finish_group();
+
+        let mut all = HittableList::new();
+        for group in &parser.groups {
+            for object in &group.triangles.objects {
+                all.add(object.clone());
+            }
+        }
+        if all.objects.is_empty() {
+            return Err(ObjError::NoGeometry(path.to_path_buf()));
+        }
+
+        let bvh = BvhNode::new(&all, 0.0..0.0, SplitMethod::SurfaceAreaHeuristic)
+            .map_err(|e| ObjError::Bvh(path.to_path_buf(), e))?;
+        Ok(Self {
+            bvh,
+            groups: parser.groups,
+        })
+    }
+
+    pub fn group(&self, name: &str) -> Option<&MeshGroup> {
+        self.groups.iter().find(|g| g.name == name)
+    }
+}
+
+impl Hittable for Mesh {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        self.bvh.hit(r, t_min, t_max)
+    }
+
+    fn bounding_box(&self, time: Range<f64>) -> Option<AABB> {
+        self.bvh.bounding_box(time)
+    }
+}
+
+/// Parses an MTL file into its materials, keyed by name.
+pub fn load_mtl(path: impl AsRef<Path>) -> Result<HashMap<String, MtlMaterial>, ObjError> {
+    let path = path.as_ref();
+    let source = read(path)?;
+    let mut materials = HashMap::new();
+    let mut current: Option<MtlMaterial> = None;
+
+    for (i, line) in source.lines().enumerate() {
+        parse_mtl_line(line, &mut current, &mut materials)
+            .map_err(|kind| parse_error(path, i + 1, kind))?;
+    }
+    if let Some(mat) = current {
+        materials.insert(mat.name.clone(), mat);
+    }
+    Ok(materials)
+}
+
+fn parse_mtl_line(
+    line: &str,
+    current: &mut Option<MtlMaterial>,
+    materials: &mut HashMap<String, MtlMaterial>,
+) -> Result<(), ParseErrorKind> {
+    let mut tokens = statement_tokens(line);
+    let keyword = match tokens.next() {
+        Some(keyword) => keyword,
+        None => return Ok(()),
+    };
+    if keyword == "newmtl" {
+        let name = rest_of_line(line, keyword).ok_or(ParseErrorKind::MissingArguments("newmtl"))?;
+        if let Some(mat) = current.replace(MtlMaterial::new(name.to_string())) {
+            materials.insert(mat.name.clone(), mat);
+        }
+        return Ok(());
+    }
+
+    let known = ["Kd", "Ks", "Ke", "Ns", "Ni", "d", "Tr", "illum"];
+    if !known.contains(&keyword) {
+        return Ok(());
+    }
+    let mat = current
+        .as_mut()
+        .ok_or(ParseErrorKind::MaterialBeforeNewmtl)?;
+    match keyword {
+        "Kd" => mat.kd = parse_color(&mut tokens, "Kd")?,
+        "Ks" => mat.ks = parse_color(&mut tokens, "Ks")?,
+        "Ke" => mat.ke = parse_color(&mut tokens, "Ke")?,
+        "Ns" => mat.ns = parse_f64(tokens.next(), "Ns")?,
+        "Ni" => mat.ni = parse_f64(tokens.next(), "Ni")?,
+        "d" => mat.d = parse_f64(tokens.next(), "d")?,
+        "Tr" => mat.d = 1.0 - parse_f64(tokens.next(), "Tr")?,
+        "illum" => {
+            let s = tokens
+                .next()
+                .ok_or(ParseErrorKind::MissingArguments("illum"))?;
+            let illum = s
+                .parse()
+                .map_err(|_| ParseErrorKind::InvalidNumber(s.to_string()))?;
+            mat.illum = Some(illum);
+        }
+        _ => unreachable!(),
+    }
+    Ok(())
+}
+
+struct ObjParser<'a> {
+    path: &'a Path,
+    positions: Vec<Point3>,
+    normals: Vec<Vec3<f64>>,
+    texcoords: Vec<Vec2<f64>>,
+    mtl_materials: HashMap<String, MtlMaterial>,
+    materials: HashMap<String, Arc<dyn Material + Send + Sync>>,
+    current_material: Arc<dyn Material + Send + Sync>,
+    current_group: MeshGroup,
+    groups: Vec<MeshGroup>,
+}
+
+#[derive(Clone, Copy)]
+struct FaceVertex {
+    position: usize,
+    texcoord: Option<usize>,
+    normal: Option<usize>,
+}
+
+impl<'a> ObjParser<'a> {
+    fn new(path: &'a Path, default_material: Arc<dyn Material + Send + Sync>) -> Self {
+        Self {
+            path,
+            positions: Vec::new(),
+            normals: Vec::new(),
+            texcoords: Vec::new(),
+            mtl_materials: HashMap::new(),
+            materials: HashMap::new(),
+            current_material: default_material,
+            current_group: MeshGroup {
+                name: String::from("default"),
+                triangles: HittableList::new(),
+            },
+            groups: Vec::new(),
+        }
+    }
+
+    fn finish_group(&mut self) {
+        if !self.current_group.triangles.objects.is_empty() {
+            let name = self.current_group.name.clone();
+            let group = std::mem::replace(
+                &mut self.current_group,
+                MeshGroup {
+                    name,
+                    triangles: HittableList::new(),
+                },
+            );
+            self.groups.push(group);
+        }
+    }
+
+    fn parse_line(&mut self, line: &str, line_number: usize) -> Result<(), ObjError> {
+        let mut tokens = statement_tokens(line);
+        if tokens.next() == Some("mtllib") {
+            //several libraries can be listed separated by spaces, but exporters also write single
+            //file names that contain spaces, so a name that exists as a whole wins
+            let files = rest_of_line(line, "mtllib").unwrap_or("");
+            let whole = self.path.with_file_name(files);
+            let paths: Vec<PathBuf> = if files.contains(char::is_whitespace) && whole.is_file() {
+                vec![whole]
+            } else {
+                files
+                    .split_whitespace()
+                    .map(|file| self.path.with_file_name(file))
+                    .collect()
+            };
+            for path in paths {
+                let materials = load_mtl(path)?;
+                self.mtl_materials.extend(materials);
+            }
+            return Ok(());
+        }
+        self.parse_statement(line)
+            .map_err(|kind| parse_error(self.path, line_number, kind))
+    }
+
+    fn parse_statement(&mut self, line: &str) -> Result<(), ParseErrorKind> {
+        let mut tokens = statement_tokens(line);
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => return Ok(()),
+        };
+        match keyword {
+            "v" => {
+                let x = parse_f64(tokens.next(), "v")?;
+                let y = parse_f64(tokens.next(), "v")?;
+                let z = parse_f64(tokens.next(), "v")?;
+                self.positions.push(Point3::new(x, y, z));
+            }
+            "vn" => {
+                let x = parse_f64(tokens.next(), "vn")?;
+                let y = parse_f64(tokens.next(), "vn")?;
+                let z = parse_f64(tokens.next(), "vn")?;
+                self.normals.push(Vec3::new(x, y, z));
+            }
+            "vt" => {
+                let u = parse_f64(tokens.next(), "vt")?;
+                let v = match tokens.next() {
+                    Some(s) => parse_f64(Some(s), "vt")?,
+                    None => 0.0,
+                };
+                self.texcoords.push(Vec2::new(u, v));
+            }
+            "f" => self.parse_face(tokens)?,
+            "g" | "o" => {
+                self.finish_group();
+                self.current_group.name =
+                    rest_of_line(line, keyword).unwrap_or("default").to_string();
+            }
+            "usemtl" => {
+                let name = rest_of_line(line, keyword)
+                    .ok_or(ParseErrorKind::MissingArguments("usemtl"))?;
+                self.current_material = self.material(name)?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn material(&mut self, name: &str) -> Result<Arc<dyn Material + Send + Sync>, ParseErrorKind> {
+        if let Some(mat) = self.materials.get(name) {
+            return Ok(mat.clone());
+        }
+        let mat = self
+            .mtl_materials
+            .get(name)
+            .ok_or_else(|| ParseErrorKind::UnknownMaterial(name.to_string()))?
+            .to_material();
+        self.materials.insert(name.to_string(), mat.clone());
+        Ok(mat)
+    }
+
+    fn parse_face<'b>(
+        &mut self,
+        tokens: impl Iterator<Item = &'b str>,
+    ) -> Result<(), ParseErrorKind> {
+        let vertices = tokens
+            .map(|token| self.parse_face_vertex(token))
+            .collect::<Result<Vec<_>, _>>()?;
+        if vertices.len() < 3 {
+            return Err(ParseErrorKind::TooFewFaceVertices(vertices.len()));
+        }
+
+        //polygons are fanned out from their first vertex
+        for i in 1..vertices.len() - 1 {
+            let corners = [vertices[0], vertices[i], vertices[i + 1]];
+            let positions = corners.map(|c| self.positions[c.position]);
+            let normals = if corners.iter().all(|c| c.normal.is_some()) {
+                Some(corners.map(|c| self.normals[c.normal.unwrap()]))
+            } else {
+                None
+            };
+            let uvs = if corners.iter().all(|c| c.texcoord.is_some()) {
+                Some(corners.map(|c| self.texcoords[c.texcoord.unwrap()]))
+            } else {
+                None
+            };
+            self.current_group.triangles.add(Arc::new(Triangle::new(
+                positions,
+                self.current_material.clone(),
+                normals,
+                uvs,
+            )));
+        }
+        Ok(())
+    }
+
+    fn parse_face_vertex(&self, token: &str) -> Result<FaceVertex, ParseErrorKind> {
+        let mut parts = token.split('/');
+        let position = resolve_index(parts.next(), self.positions.len())?
+            .ok_or_else(|| ParseErrorKind::InvalidIndex(token.to_string()))?;
+        let texcoord = resolve_index(parts.next(), self.texcoords.len())?;
+        let normal = resolve_index(parts.next(), self.normals.len())?;
+        Ok(FaceVertex {
+            position,
+            texcoord,
+            normal,
+        })
+    }
+}
+
+/// Turns a one-based (or negative, relative) OBJ index into a zero-based one.
+fn resolve_index(s: Option<&str>, len: usize) -> Result<Option<usize>, ParseErrorKind> {
+    let s = match s {
+        Some(s) if !s.is_empty() => s,
+        _ => return Ok(None),
+    };
+    let i: i64 = s
+        .parse()
+        .map_err(|_| ParseErrorKind::InvalidIndex(s.to_string()))?;
+    let resolved = if i > 0 { i - 1 } else { len as i64 + i };
+    if i == 0 || resolved < 0 || resolved >= len as i64 {
+        return Err(ParseErrorKind::IndexOutOfRange(i));
+    }
+    Ok(Some(resolved as usize))
+}
+
+fn statement_tokens(line: &str) -> std::str::SplitWhitespace<'_> {
+    let line = match line.find('#') {
+        Some(i) => &line[..i],
+        None => line,
+    };
+    line.split_whitespace()
+}
+
+/// Names may contain spaces, so they run from after the keyword to the end of the line.
+fn rest_of_line<'a>(line: &'a str, keyword: &str) -> Option<&'a str> {
+    let line = match line.find('#') {
+        Some(i) => &line[..i],
+        None => line,
+    };
+    let rest = line.trim_start()[keyword.len()..].trim();
+    if rest.is_empty() {
+        None
+    } else {
+        Some(rest)
+    }
+}
+
+fn parse_f64(s: Option<&str>, statement: &'static str) -> Result<f64, ParseErrorKind> {
+    let s = s.ok_or(ParseErrorKind::MissingArguments(statement))?;
+    //`parse` takes "nan" and "inf", which no OBJ or MTL value can sensibly be
+    match s.parse::<f64>() {
+        Ok(x) if x.is_finite() => Ok(x),
+        _ => Err(ParseErrorKind::InvalidNumber(s.to_string())),
+    }
+}
+
+fn parse_color<'a>(
+    tokens: &mut impl Iterator<Item = &'a str>,
+    statement: &'static str,
+) -> Result<Color, ParseErrorKind> {
+    let r = parse_f64(tokens.next(), statement)?;
+    //a single value sets all three channels
+    match tokens.next() {
+        Some(g) => {
+            let g = parse_f64(Some(g), statement)?;
+            let b = parse_f64(tokens.next(), statement)?;
+            Ok(Color::new(r, g, b))
+        }
+        None => Ok(Color::new(r, r, r)),
+    }
+}
+
+fn read(path: &Path) -> Result<String, ObjError> {
+    fs::read_to_string(path).map_err(|e| ObjError::Io(path.to_path_buf(), e))
+}
+
+fn parse_error(path: &Path, line: usize, kind: ParseErrorKind) -> ObjError {
+    ObjError::Parse {
+        path: path.to_path_buf(),
+        line,
+        kind,
+    }
+}
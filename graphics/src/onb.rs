@@ -0,0 +1,64 @@
+use crate::utils::unit_vector;
+use vek::vec::Vec3;
+
+/// An orthonormal basis, for building directions around a normal or some other axis.
+#[derive(Clone, Copy, Debug)]
+pub struct Onb {
+    pub u: Vec3<f64>,
+    pub v: Vec3<f64>,
+    pub w: Vec3<f64>,
+}
+
+impl Onb {
+    /// A basis whose `w` axis points along `n`. The other two axes are arbitrary.
+    pub fn from_w(n: Vec3<f64>) -> Self {
+        let w = unit_vector(n);
+        //any vector that isn't nearly parallel to w will do
+        let helper = if w.x.abs() > 0.9 {
+            Vec3::new(0.0, 1.0, 0.0)
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let u = unit_vector(Vec3::cross(w, helper));
+        let v = Vec3::cross(w, u);
+        Self { u, v, w }
+    }
+
+    /// A basis whose `w` axis points along `n` and whose `u` axis is `tangent` made perpendicular
+    /// to it, so that the axes follow a surface's parameterization. Falls back to `from_w` when
+    /// the tangent is zero or parallel to `n`.
+    pub fn from_w_and_u(n: Vec3<f64>, tangent: Vec3<f64>) -> Self {
+        let w = unit_vector(n);
+        let u = tangent - Vec3::dot(tangent, w) * w;
+        if u.magnitude_squared() <= 1e-12 * tangent.magnitude_squared().max(1e-300) {
+            return Self::from_w(w);
+        }
+        let u = unit_vector(u);
+        let v = Vec3::cross(w, u);
+        Self { u, v, w }
+    }
+
+    /// Takes `a` from this basis into world space.
+    pub fn local(&self, a: Vec3<f64>) -> Vec3<f64> {
+        a.x * self.u + a.y * self.v + a.z * self.w
+    }
+
+    /// The inverse of `local`: the coordinates of `a` in this basis.
+    pub fn to_local(&self, a: Vec3<f64>) -> Vec3<f64> {
+        Vec3::new(
+            Vec3::dot(a, self.u),
+            Vec3::dot(a, self.v),
+            Vec3::dot(a, self.w),
+        )
+    }
+
+    /// The spherical direction `theta` away from `w` and `phi` around it, given as cosines and
+    /// sines.
+    pub fn spherical(&self, cos_theta: f64, sin_theta: f64, phi: f64) -> Vec3<f64> {
+        self.local(Vec3::new(
+            sin_theta * phi.cos(),
+            sin_theta * phi.sin(),
+            cos_theta,
+        ))
+    }
+}
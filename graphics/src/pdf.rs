@@ -0,0 +1,111 @@
+use crate::hittable::Hittable;
+use crate::onb::Onb;
+use crate::sampler::Sampler;
+use crate::utils::*;
+use crate::Point3;
+use std::f64::consts::PI;
+use vek::vec::Vec3;
+
+/// A distribution of directions that can be both sampled and evaluated, so that samples drawn
+/// from it can be weighted by how likely they were.
+pub trait Pdf {
+    /// The density per unit solid angle with which `generate` picks `direction`.
+    fn value(&self, direction: &Vec3<f64>) -> f64;
+
+    /// A direction drawn from the distribution. It need not be normalized.
+    fn generate(&self, sampler: &mut dyn Sampler) -> Vec3<f64>;
+}
+
+/// Every direction equally likely.
+pub struct SpherePdf;
+
+impl Pdf for SpherePdf {
+    fn value(&self, _direction: &Vec3<f64>) -> f64 {
+        1.0 / (4.0 * PI)
+    }
+
+    fn generate(&self, sampler: &mut dyn Sampler) -> Vec3<f64> {
+        random_unit_vector(sampler)
+    }
+}
+
+/// Directions over the hemisphere around a normal, in proportion to their cosine with it.
+pub struct CosinePdf {
+    uvw: Onb,
+}
+
+impl CosinePdf {
+    pub fn new(normal: Vec3<f64>) -> Self {
+        Self {
+            uvw: Onb::from_w(normal),
+        }
+    }
+}
+
+impl Pdf for CosinePdf {
+    fn value(&self, direction: &Vec3<f64>) -> f64 {
+        let cosine = Vec3::dot(unit_vector(*direction), self.uvw.w);
+        cosine.max(0.0) / PI
+    }
+
+    //lifting points spread evenly over the disk up onto the hemisphere gives the cosine falloff
+    fn generate(&self, sampler: &mut dyn Sampler) -> Vec3<f64> {
+        let disk = random_in_unit_disk(sampler);
+        let z = (1.0 - disk.x * disk.x - disk.y * disk.y).max(0.0).sqrt();
+        self.uvw.local(Vec3::new(disk.x, disk.y, z))
+    }
+}
+
+/// Directions from `origin` towards the objects, as picked by `Hittable::random`.
+pub struct HittablePdf<'a> {
+    objects: &'a dyn Hittable,
+    origin: Point3,
+}
+
+impl<'a> HittablePdf<'a> {
+    pub fn new(objects: &'a dyn Hittable, origin: Point3) -> Self {
+        Self { objects, origin }
+    }
+}
+
+impl Pdf for HittablePdf<'_> {
+    fn value(&self, direction: &Vec3<f64>) -> f64 {
+        self.objects.pdf_value(&self.origin, direction)
+    }
+
+    fn generate(&self, sampler: &mut dyn Sampler) -> Vec3<f64> {
+        self.objects.random(&self.origin, sampler)
+    }
+}
+
+/// Draws from `a` with probability `weight` and from `b` otherwise.
+pub struct MixturePdf<'a> {
+    a: &'a dyn Pdf,
+    b: &'a dyn Pdf,
+    weight: f64,
+}
+
+impl<'a> MixturePdf<'a> {
+    /// `weight` is clamped to [0, 1].
+    pub fn new(a: &'a dyn Pdf, b: &'a dyn Pdf, weight: f64) -> Self {
+        Self {
+            a,
+            b,
+            weight: weight.clamp(0.0, 1.0),
+        }
+    }
+}
+
+impl Pdf for MixturePdf<'_> {
+    fn value(&self, direction: &Vec3<f64>) -> f64 {
+        self.weight * self.a.value(direction) + (1.0 - self.weight) * self.b.value(direction)
+    }
+
+    fn generate(&self, sampler: &mut dyn Sampler) -> Vec3<f64> {
+        if sampler.get_1d() < self.weight {
+            self.a.generate(sampler)
+        } else {
+            self.b.generate(sampler)
+        }
+    }
+}
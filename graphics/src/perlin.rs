@@ -0,0 +1,105 @@
+use crate::rng::Rng;
+use crate::utils::unit_vector;
+use crate::Point3;
+use vek::vec::Vec3;
+
+const POINT_COUNT: usize = 256;
+
+/// Gradient noise over 3D space. The same seed always produces the same noise field.
+#[derive(Clone)]
+pub struct Perlin {
+    ranvec: Vec<Vec3<f64>>,
+    perm_x: Vec<usize>,
+    perm_y: Vec<usize>,
+    perm_z: Vec<usize>,
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Rng::new(seed);
+        let ranvec = (0..POINT_COUNT)
+            .map(|_| {
+                unit_vector(Vec3::new(
+                    rng.rand_f64_range(-1.0, 1.0),
+                    rng.rand_f64_range(-1.0, 1.0),
+                    rng.rand_f64_range(-1.0, 1.0),
+                ))
+            })
+            .collect();
+
+        Self {
+            ranvec,
+            perm_x: Self::generate_perm(&mut rng),
+            perm_y: Self::generate_perm(&mut rng),
+            perm_z: Self::generate_perm(&mut rng),
+        }
+    }
+
+    fn generate_perm(rng: &mut Rng) -> Vec<usize> {
+        let mut p: Vec<usize> = (0..POINT_COUNT).collect();
+        for i in (1..POINT_COUNT).rev() {
+            let target = rng.rand_index(i + 1);
+            p.swap(i, target);
+        }
+        p
+    }
+
+    /// Noise in roughly [-1, 1], smoothly varying with `p`.
+    pub fn noise(&self, p: &Point3) -> f64 {
+        let floor = p.map(f64::floor);
+        let frac = *p - floor;
+        let i = floor.x as i64;
+        let j = floor.y as i64;
+        let k = floor.z as i64;
+
+        let mut c = [[[Vec3::zero(); 2]; 2]; 2];
+        for (di, plane) in c.iter_mut().enumerate() {
+            for (dj, row) in plane.iter_mut().enumerate() {
+                for (dk, corner) in row.iter_mut().enumerate() {
+                    *corner = self.ranvec[self.perm_x[((i + di as i64) & 255) as usize]
+                        ^ self.perm_y[((j + dj as i64) & 255) as usize]
+                        ^ self.perm_z[((k + dk as i64) & 255) as usize]];
+                }
+            }
+        }
+
+        Self::perlin_interp(&c, frac)
+    }
+
+    /// Sums `octaves` layers of noise, each at double the frequency and half the weight, and
+    /// returns the absolute value of the total.
+    pub fn turb(&self, p: &Point3, octaves: usize) -> f64 {
+        let mut accum = 0.0;
+        let mut temp_p = *p;
+        let mut weight = 1.0;
+
+        for _ in 0..octaves {
+            accum += weight * self.noise(&temp_p);
+            weight *= 0.5;
+            temp_p *= 2.0;
+        }
+
+        accum.abs()
+    }
+
+    //trilinear interpolation of the corner gradients, eased with a Hermite cubic
+    fn perlin_interp(c: &[[[Vec3<f64>; 2]; 2]; 2], frac: Vec3<f64>) -> f64 {
+        let smooth = frac.map(|t| t * t * (3.0 - 2.0 * t));
+        let mut accum = 0.0;
+
+        for (i, plane) in c.iter().enumerate() {
+            for (j, row) in plane.iter().enumerate() {
+                for (k, corner) in row.iter().enumerate() {
+                    let (fi, fj, fk) = (i as f64, j as f64, k as f64);
+                    let weight_v = frac - Vec3::new(fi, fj, fk);
+                    accum += (fi * smooth.x + (1.0 - fi) * (1.0 - smooth.x))
+                        * (fj * smooth.y + (1.0 - fj) * (1.0 - smooth.y))
+                        * (fk * smooth.z + (1.0 - fk) * (1.0 - smooth.z))
+                        * Vec3::dot(*corner, weight_v);
+                }
+            }
+        }
+
+        accum
+    }
+}
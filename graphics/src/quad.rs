@@ -0,0 +1,210 @@
+use crate::aabb::{AABB, BOX_PADDING};
+use crate::hittable::{HitRecord, Hittable};
+use crate::hittable_list::HittableList;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::utils::{area_to_solid_angle_pdf, unit_vector};
+use crate::Point3;
+use std::ops::Range;
+use std::sync::Arc;
+use vek::vec::Vec3;
+
+/// A parallelogram with one corner at `q` and sides `u` and `v`.
+///
+/// The front face is the one that `u` cross `v` points out of.
+pub struct Quad {
+    q: Point3,
+    u: Vec3<f64>,
+    v: Vec3<f64>,
+    mat_ptr: Arc<dyn Material + Send + Sync>,
+    normal: Vec3<f64>,
+    d: f64,
+    w: Vec3<f64>,
+    area: f64,
+}
+
+impl Quad {
+    pub fn new(
+        q: Point3,
+        u: Vec3<f64>,
+        v: Vec3<f64>,
+        mat_ptr: Arc<dyn Material + Send + Sync>,
+    ) -> Self {
+        let n = Vec3::cross(u, v);
+        let normal = unit_vector(n);
+        Self {
+            q,
+            u,
+            v,
+            mat_ptr,
+            normal,
+            d: Vec3::dot(normal, q),
+            w: n / Vec3::dot(n, n),
+            area: n.magnitude(),
+        }
+    }
+
+    /// A rectangle in the plane z = k, facing +z.
+    pub fn xy_rect(
+        x: Range<f64>,
+        y: Range<f64>,
+        k: f64,
+        mat_ptr: Arc<dyn Material + Send + Sync>,
+    ) -> Self {
+        Self::new(
+            Point3::new(x.start, y.start, k),
+            Vec3::new(x.end - x.start, 0.0, 0.0),
+            Vec3::new(0.0, y.end - y.start, 0.0),
+            mat_ptr,
+        )
+    }
+
+    /// A rectangle in the plane y = k, facing +y.
+    pub fn xz_rect(
+        x: Range<f64>,
+        z: Range<f64>,
+        k: f64,
+        mat_ptr: Arc<dyn Material + Send + Sync>,
+    ) -> Self {
+        Self::new(
+            Point3::new(x.start, k, z.start),
+            Vec3::new(0.0, 0.0, z.end - z.start),
+            Vec3::new(x.end - x.start, 0.0, 0.0),
+            mat_ptr,
+        )
+    }
+
+    /// A rectangle in the plane x = k, facing +x.
+    pub fn yz_rect(
+        y: Range<f64>,
+        z: Range<f64>,
+        k: f64,
+        mat_ptr: Arc<dyn Material + Send + Sync>,
+    ) -> Self {
+        Self::new(
+            Point3::new(k, y.start, z.start),
+            Vec3::new(0.0, y.end - y.start, 0.0),
+            Vec3::new(0.0, 0.0, z.end - z.start),
+            mat_ptr,
+        )
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let denom = Vec3::dot(self.normal, r.direction);
+        //the ray is parallel to the plane
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = (self.d - Vec3::dot(self.normal, r.origin)) / denom;
+        if t < t_min || t > t_max {
+            return None;
+        }
+
+        //express the hit point in the (u, v) frame of the quad to test that it lies inside
+        let p = r.at(t);
+        let planar_hitpt = p - self.q;
+        let alpha = Vec3::dot(self.w, Vec3::cross(planar_hitpt, self.v));
+        let beta = Vec3::dot(self.w, Vec3::cross(self.u, planar_hitpt));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return None;
+        }
+
+        Some(HitRecord {
+            p,
+            normal: HitRecord::set_face_normal(r, self.normal),
+            geometric_normal: HitRecord::set_face_normal(r, self.normal),
+            tangent: self.u,
+            bitangent: self.v,
+            mat_ptr: self.mat_ptr.clone(),
+            t,
+            u: alpha,
+            v: beta,
+            front_face: denom < 0.0,
+        })
+    }
+
+    fn bounding_box(&self, _time: Range<f64>) -> Option<AABB> {
+        let diagonal1 = AABB::new(
+            Vec3::partial_min(self.q, self.q + self.u + self.v),
+            Vec3::partial_max(self.q, self.q + self.u + self.v),
+        );
+        let diagonal2 = AABB::new(
+            Vec3::partial_min(self.q + self.u, self.q + self.v),
+            Vec3::partial_max(self.q + self.u, self.q + self.v),
+        );
+        Some(AABB::surrounding_box(diagonal1, diagonal2).padded(BOX_PADDING))
+    }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3<f64>) -> f64 {
+        match self.hit(&Ray::new(*origin, *direction, 0.0), 0.001, f64::INFINITY) {
+            Some(rec) => area_to_solid_angle_pdf(self.area, self.normal, *direction, rec.t),
+            None => 0.0,
+        }
+    }
+
+    fn random(&self, origin: &Point3, sampler: &mut dyn Sampler) -> Vec3<f64> {
+        let xi = sampler.get_2d();
+        self.q + xi.x * self.u + xi.y * self.v - *origin
+    }
+}
+
+/// An axis-aligned box made of six outward-facing quads.
+pub struct Cuboid {
+    sides: HittableList,
+    bbox: AABB,
+}
+
+impl Cuboid {
+    /// `a` and `b` are any two opposite corners of the box.
+    pub fn new(a: Point3, b: Point3, mat_ptr: Arc<dyn Material + Send + Sync>) -> Self {
+        let min = Vec3::partial_min(a, b);
+        let max = Vec3::partial_max(a, b);
+
+        let dx = Vec3::new(max.x - min.x, 0.0, 0.0);
+        let dy = Vec3::new(0.0, max.y - min.y, 0.0);
+        let dz = Vec3::new(0.0, 0.0, max.z - min.z);
+
+        let mut sides = HittableList::new();
+        let faces = [
+            (Point3::new(min.x, min.y, max.z), dx, dy),  //front
+            (Point3::new(max.x, min.y, max.z), -dz, dy), //right
+            (Point3::new(max.x, min.y, min.z), -dx, dy), //back
+            (Point3::new(min.x, min.y, min.z), dz, dy),  //left
+            (Point3::new(min.x, max.y, max.z), dx, -dz), //top
+            (Point3::new(min.x, min.y, min.z), dx, dz),  //bottom
+        ];
+        for (q, u, v) in faces {
+            sides.add(Arc::new(Quad::new(q, u, v, mat_ptr.clone())));
+        }
+
+        Self {
+            sides,
+            bbox: AABB::new(min, max).padded(BOX_PADDING),
+        }
+    }
+}
+
+impl Hittable for Cuboid {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        if !self.bbox.hit(r, t_min, t_max) {
+            return None;
+        }
+        self.sides.hit(r, t_min, t_max)
+    }
+
+    fn bounding_box(&self, _time: Range<f64>) -> Option<AABB> {
+        Some(self.bbox)
+    }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3<f64>) -> f64 {
+        self.sides.pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: &Point3, sampler: &mut dyn Sampler) -> Vec3<f64> {
+        self.sides.random(origin, sampler)
+    }
+}
@@ -0,0 +1,73 @@
+/// A small seedable PCG32 generator, the source of every random number in the renderer.
+///
+/// Unlike `thread_rng`, its output depends only on how it was seeded, so the same seed always
+/// produces the same scene and the same image.
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+const MULTIPLIER: u64 = 6364136223846793005;
+const INCREMENT: u64 = 1442695040888963407;
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Self { state: 0 };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+        rng
+    }
+
+    /// Seeds from several words at once, so that nearby inputs still give unrelated sequences.
+    pub fn from_words(words: &[u64]) -> Self {
+        Self::new(hash(words))
+    }
+
+    /// The generator for one sample of one pixel. Every (pixel, sample) pair gets a sequence of
+    /// its own, so the image doesn't depend on which thread rendered which pixel.
+    pub fn for_sample(seed: u64, pixel: usize, sample: usize) -> Self {
+        Self::from_words(&[seed, pixel as u64, sample as u64])
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(MULTIPLIER).wrapping_add(INCREMENT);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        let rot = (old >> 59) as u32;
+        xorshifted.rotate_right(rot)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        ((self.next_u32() as u64) << 32) | self.next_u32() as u64
+    }
+
+    /// Uniform in [0, 1).
+    pub fn rand_f64(&mut self) -> f64 {
+        //the top 53 bits fill the whole mantissa
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+
+    pub fn rand_f64_range(&mut self, min: f64, max: f64) -> f64 {
+        min + (max - min) * self.rand_f64()
+    }
+
+    /// Uniform in `0..n`.
+    pub fn rand_index(&mut self, n: usize) -> usize {
+        ((self.rand_f64() * n as f64) as usize).min(n - 1)
+    }
+}
+
+/// Mixes several words into one well-distributed value.
+pub fn hash(words: &[u64]) -> u64 {
+    words
+        .iter()
+        .fold(0x853c49e6748fea9b, |hash, &w| mix(hash ^ mix(w)))
+}
+
+//the splitmix64 finalizer
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
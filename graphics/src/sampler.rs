@@ -0,0 +1,451 @@
+use crate::rng::{hash, Rng};
+use vek::vec::Vec2;
+
+//where each part of a path reads its samples from. Every bounce gets a fixed block of dimensions,
+//so a material that uses fewer than the whole block doesn't shift the ones after it. The first
+//half of a bounce's block is for scattering and the second half for picking a point on a light,
+//except for the last dimension, which decides whether the path survives Russian roulette
+pub const PIXEL_DIMENSION: usize = 0;
+pub const LENS_DIMENSION: usize = 2;
+pub const TIME_DIMENSION: usize = 4;
+pub const FIRST_BOUNCE_DIMENSION: usize = 5;
+pub const BOUNCE_DIMENSIONS: usize = 8;
+pub const LIGHT_DIMENSION_OFFSET: usize = 4;
+pub const ROULETTE_DIMENSION_OFFSET: usize = 7;
+
+/// Hands out the sample values in [0, 1) used to build one path through a pixel.
+///
+/// Values are addressed by dimension, and the same dimension of every sample in a pixel comes
+/// from one well-spread set of points. Each call to `get_1d` or `get_2d` moves on to the next
+/// dimension.
+pub trait Sampler {
+    fn start_pixel_sample(&mut self, pixel: usize, sample_index: usize);
+
+    /// Moves the next value to be read to `dimension`.
+    fn start_dimension(&mut self, dimension: usize);
+
+    fn get_1d(&mut self) -> f64;
+
+    fn get_2d(&mut self) -> Vec2<f64>;
+
+    /// The offset of the sample within its pixel.
+    fn pixel_2d(&mut self) -> Vec2<f64> {
+        self.start_dimension(PIXEL_DIMENSION);
+        self.get_2d()
+    }
+
+    fn lens_2d(&mut self) -> Vec2<f64> {
+        self.start_dimension(LENS_DIMENSION);
+        self.get_2d()
+    }
+
+    fn time_1d(&mut self) -> f64 {
+        self.start_dimension(TIME_DIMENSION);
+        self.get_1d()
+    }
+
+    /// Skips to the block of dimensions for `bounce`, counted from 0 at the camera ray's hit.
+    fn start_bounce(&mut self, bounce: usize) {
+        self.start_dimension(FIRST_BOUNCE_DIMENSION + bounce * BOUNCE_DIMENSIONS);
+    }
+
+    /// Skips to the dimensions used to sample a light from the hit at `bounce`.
+    fn start_light(&mut self, bounce: usize) {
+        self.start_dimension(
+            FIRST_BOUNCE_DIMENSION + bounce * BOUNCE_DIMENSIONS + LIGHT_DIMENSION_OFFSET,
+        );
+    }
+
+    /// Skips to the dimension that decides whether the path ends after the hit at `bounce`.
+    fn start_roulette(&mut self, bounce: usize) {
+        self.start_dimension(
+            FIRST_BOUNCE_DIMENSION + bounce * BOUNCE_DIMENSIONS + ROULETTE_DIMENSION_OFFSET,
+        );
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SamplerKind {
+    #[default]
+    Independent,
+    Stratified,
+    Halton,
+    Sobol,
+}
+
+impl SamplerKind {
+    pub fn build(&self, seed: u64, samples_per_pixel: usize) -> Box<dyn Sampler> {
+        match self {
+            SamplerKind::Independent => Box::new(IndependentSampler::new(seed)),
+            SamplerKind::Stratified => Box::new(StratifiedSampler::new(seed, samples_per_pixel)),
+            SamplerKind::Halton => Box::new(HaltonSampler::new(seed)),
+            SamplerKind::Sobol => Box::new(SobolSampler::new(seed)),
+        }
+    }
+}
+
+/// Plain uniform random numbers with no structure across samples.
+pub struct IndependentSampler {
+    seed: u64,
+    rng: Rng,
+}
+
+impl IndependentSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: Rng::new(seed),
+        }
+    }
+}
+
+impl Sampler for IndependentSampler {
+    fn start_pixel_sample(&mut self, pixel: usize, sample_index: usize) {
+        self.rng = Rng::for_sample(self.seed, pixel, sample_index);
+    }
+
+    fn start_dimension(&mut self, _dimension: usize) {}
+
+    fn get_1d(&mut self) -> f64 {
+        self.rng.rand_f64()
+    }
+
+    fn get_2d(&mut self) -> Vec2<f64> {
+        Vec2::new(self.rng.rand_f64(), self.rng.rand_f64())
+    }
+}
+
+/// Where the current sample is, shared by the samplers that address values by dimension.
+#[derive(Clone, Copy, Default)]
+struct SamplePosition {
+    pixel: usize,
+    sample_index: usize,
+    dimension: usize,
+}
+
+impl SamplePosition {
+    fn next_dimension(&mut self) -> usize {
+        let dimension = self.dimension;
+        self.dimension += 1;
+        dimension
+    }
+
+    //a value unique to this pixel and dimension but shared by all of the pixel's samples
+    fn scramble(&self, seed: u64, dimension: usize) -> u64 {
+        hash(&[seed, self.pixel as u64, dimension as u64])
+    }
+
+    fn rng(&self, seed: u64, dimension: usize) -> Rng {
+        Rng::from_words(&[
+            seed,
+            self.pixel as u64,
+            self.sample_index as u64,
+            dimension as u64,
+        ])
+    }
+}
+
+/// Jittered grid sampling: each dimension is split into one stratum per sample and every sample
+/// lands in a different one, with the strata shuffled independently for each dimension.
+///
+/// Pairs of dimensions are split into an `nx` by `ny` grid with exactly one cell per sample, so
+/// the cells are only square when the sample count is. Counts with no factors near their square
+/// root, like primes, get long thin cells.
+pub struct StratifiedSampler {
+    seed: u64,
+    samples_per_pixel: usize,
+    //the columns and rows of the 2D strata
+    grid: (usize, usize),
+    position: SamplePosition,
+}
+
+impl StratifiedSampler {
+    pub fn new(seed: u64, samples_per_pixel: usize) -> Self {
+        let samples_per_pixel = samples_per_pixel.max(1);
+        //the most square grid whose cells the samples fill exactly
+        let root = (samples_per_pixel as f64).sqrt() as usize;
+        //`is_multiple_of` would need a newer compiler than the rest of the crate does
+        #[allow(clippy::manual_is_multiple_of)]
+        let nx = (1..=root)
+            .rev()
+            .find(|&n| samples_per_pixel % n == 0)
+            .unwrap_or(1);
+        Self {
+            seed,
+            samples_per_pixel,
+            grid: (nx, samples_per_pixel / nx),
+            position: SamplePosition::default(),
+        }
+    }
+
+    fn stratum(&self, dimension: usize, strata: usize) -> usize {
+        let index = self.position.sample_index % strata;
+        let scramble = self.position.scramble(self.seed, dimension) as u32;
+        permutation_element(index as u32, strata as u32, scramble) as usize
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn start_pixel_sample(&mut self, pixel: usize, sample_index: usize) {
+        self.position = SamplePosition {
+            pixel,
+            sample_index,
+            dimension: 0,
+        };
+    }
+
+    fn start_dimension(&mut self, dimension: usize) {
+        self.position.dimension = dimension;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let dimension = self.position.next_dimension();
+        let strata = self.samples_per_pixel;
+        let jitter = self.position.rng(self.seed, dimension).rand_f64();
+        (self.stratum(dimension, strata) as f64 + jitter) / strata as f64
+    }
+
+    fn get_2d(&mut self) -> Vec2<f64> {
+        let dimension = self.position.next_dimension();
+        let (nx, ny) = self.grid;
+        let stratum = self.stratum(dimension, nx * ny);
+        let mut rng = self.position.rng(self.seed, dimension);
+        Vec2::new(
+            ((stratum % nx) as f64 + rng.rand_f64()) / nx as f64,
+            ((stratum / nx) as f64 + rng.rand_f64()) / ny as f64,
+        )
+    }
+}
+
+const PRIMES: [u64; 64] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191, 193,
+    197, 199, 211, 223, 227, 229, 233, 239, 241, 251, 257, 263, 269, 271, 277, 281, 283, 293, 307,
+    311,
+];
+
+/// The Halton sequence, with dimension `d` the radical inverse in the `d`th prime base.
+///
+/// The digits are Owen scrambled separately for every pixel and dimension. Without that the
+/// larger bases put the first few dozen samples in a tiny corner of [0, 1).
+///
+/// Dimensions past the 64th, which only very deep paths reach, fall back to random numbers.
+pub struct HaltonSampler {
+    seed: u64,
+    position: SamplePosition,
+}
+
+impl HaltonSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            position: SamplePosition::default(),
+        }
+    }
+
+    fn sample(&self, dimension: usize) -> f64 {
+        match PRIMES.get(dimension) {
+            Some(&base) => owen_scrambled_radical_inverse(
+                base,
+                self.position.sample_index as u64,
+                self.position.scramble(self.seed, dimension),
+            ),
+            None => self.position.rng(self.seed, dimension).rand_f64(),
+        }
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn start_pixel_sample(&mut self, pixel: usize, sample_index: usize) {
+        self.position = SamplePosition {
+            pixel,
+            sample_index,
+            dimension: 0,
+        };
+    }
+
+    fn start_dimension(&mut self, dimension: usize) {
+        self.position.dimension = dimension;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let dimension = self.position.next_dimension();
+        self.sample(dimension)
+    }
+
+    fn get_2d(&mut self) -> Vec2<f64> {
+        let x = self.get_1d();
+        let y = self.get_1d();
+        Vec2::new(x, y)
+    }
+}
+
+/// The first two dimensions of the Sobol sequence with Owen scrambling, as described by Burley in
+/// "Practical Hash-based Owen Scrambling".
+///
+/// Each dimension (or pair of dimensions for `get_2d`) uses its own shuffle of the points, which
+/// keeps the dimensions of a path from being correlated while needing no direction number tables.
+/// Works best with a power of two samples per pixel.
+pub struct SobolSampler {
+    seed: u64,
+    position: SamplePosition,
+}
+
+impl SobolSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            position: SamplePosition::default(),
+        }
+    }
+
+    fn sample_2d(&self, dimension: usize) -> (u32, u32) {
+        let scramble = self.position.scramble(self.seed, dimension);
+        let index = nested_uniform_scramble(self.position.sample_index as u32, scramble as u32);
+        let x = nested_uniform_scramble(index.reverse_bits(), hash(&[scramble, 0]) as u32);
+        let y = nested_uniform_scramble(sobol_second_dimension(index), hash(&[scramble, 1]) as u32);
+        (x, y)
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn start_pixel_sample(&mut self, pixel: usize, sample_index: usize) {
+        self.position = SamplePosition {
+            pixel,
+            sample_index,
+            dimension: 0,
+        };
+    }
+
+    fn start_dimension(&mut self, dimension: usize) {
+        self.position.dimension = dimension;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let dimension = self.position.next_dimension();
+        to_unit(self.sample_2d(dimension).0 as u64)
+    }
+
+    fn get_2d(&mut self) -> Vec2<f64> {
+        let dimension = self.position.next_dimension();
+        let (x, y) = self.sample_2d(dimension);
+        Vec2::new(to_unit(x as u64), to_unit(y as u64))
+    }
+}
+
+//maps a 32 bit value to [0, 1)
+fn to_unit(x: u64) -> f64 {
+    (x & 0xffff_ffff) as f64 / (1u64 << 32) as f64
+}
+
+//mirrors the digits of index about the radix point, permuting each digit by a hash of the digits
+//before it. The zero digits past the end of index get permuted too, until they stop mattering
+fn owen_scrambled_radical_inverse(base: u64, mut index: u64, scramble: u64) -> f64 {
+    let inv_base = 1.0 / base as f64;
+    let mut inv_base_n = 1.0;
+    let mut reversed: u64 = 0;
+    while 1.0 - (base - 1) as f64 * inv_base_n < 1.0 {
+        //for the larger bases that test outlasts the digits `reversed` can hold, and digits past
+        //those are far too small to change the result anyway
+        let shifted = match reversed.checked_mul(base) {
+            Some(shifted) if shifted.checked_add(base - 1).is_some() => shifted,
+            _ => break,
+        };
+        let next = index / base;
+        let digit_hash = hash(&[scramble, reversed]) as u32;
+        let digit = permutation_element((index - next * base) as u32, base as u32, digit_hash);
+        reversed = shifted + digit as u64;
+        inv_base_n *= inv_base;
+        index = next;
+    }
+    (reversed as f64 * inv_base_n).min(1.0 - f64::EPSILON)
+}
+
+//the direction numbers of the second Sobol dimension are each the previous one xor itself shifted
+fn sobol_second_dimension(mut index: u32) -> u32 {
+    let mut x = 0;
+    let mut v = 1 << 31;
+    while index != 0 {
+        if index & 1 != 0 {
+            x ^= v;
+        }
+        index >>= 1;
+        v ^= v >> 1;
+    }
+    x
+}
+
+//Laine and Karras' hash, which only lets each bit be affected by the bits below it
+fn laine_karras_permutation(mut x: u32, seed: u32) -> u32 {
+    x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50b47c);
+    x ^= x.wrapping_mul(0xb82f1e52);
+    x ^= x.wrapping_mul(0xc7afe638);
+    x ^= x.wrapping_mul(0x8d22f6e6);
+    x
+}
+
+//an Owen scramble: each bit is flipped depending only on the bits above it
+fn nested_uniform_scramble(x: u32, seed: u32) -> u32 {
+    laine_karras_permutation(x.reverse_bits(), seed).reverse_bits()
+}
+
+//Kensler's "Correlated Multi-Jittered Sampling": element i of a random permutation of 0..l
+fn permutation_element(mut i: u32, l: u32, p: u32) -> u32 {
+    let mut w = l - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+    loop {
+        i ^= p;
+        i = i.wrapping_mul(0xe170893d);
+        i ^= p >> 16;
+        i ^= (i & w) >> 4;
+        i ^= p >> 8;
+        i = i.wrapping_mul(0x0929eb3f);
+        i ^= p >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | p >> 27);
+        i = i.wrapping_mul(0x6935fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dcb303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e501cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < l {
+            return (i.wrapping_add(p)) % l;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn halton_dimensions_average_to_one_half() {
+        const SAMPLES: usize = 1024;
+        let mut sampler = HaltonSampler::new(0);
+        for dimension in 0..PRIMES.len() {
+            let mean = (0..SAMPLES)
+                .map(|i| {
+                    sampler.start_pixel_sample(7, i);
+                    sampler.start_dimension(dimension);
+                    sampler.get_1d()
+                })
+                .sum::<f64>()
+                / SAMPLES as f64;
+            assert!(
+                (mean - 0.5).abs() < 0.02,
+                "dimension {} averages {}",
+                dimension,
+                mean
+            );
+        }
+    }
+}
@@ -1,14 +1,29 @@
-use crate::hittable::{HitRecord, Hittable};
+use crate::constant_medium::ConstantMedium;
 use crate::hittable_list::HittableList;
-use crate::material::{Dielectric, Lambertian, Material, Metal};
+use crate::material::{
+    ComplexIor, Dielectric, DiffuseLight, HenyeyGreenstein, Isotropic, Lambertian, Material, Metal,
+    NormalMapped, Param, Principled, PrincipledParams, RoughConductor, RoughDielectric,
+};
+use crate::microfacet::MicrofacetDistribution;
+use crate::quad::{Cuboid, Quad};
+use crate::rng::Rng;
 use crate::sphere::{Movement, Sphere};
+use crate::texture::{
+    CheckerTexture, ColorSpace, ImageTexture, ImageTextureError, MarbleTexture, NoiseTexture,
+    TextureFilter, UvCheckerTexture, WoodTexture, WrapMode,
+};
+use crate::transform::Transform;
 use crate::utils::*;
 use crate::Color;
 use crate::Point3;
 use std::ops::Range;
+use std::path::Path;
 use std::sync::Arc;
+use vek::mat::Mat4;
+use vek::vec::Vec3;
 
-pub fn random_scene(time: &Option<Range<f64>>) -> HittableList {
+pub fn random_scene(time: &Option<Range<f64>>, seed: u64) -> HittableList {
+    let mut rng = Rng::new(seed);
     let mut world = HittableList::new();
     let ground_mat = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
     world.add(Arc::new(Sphere::new(
@@ -20,28 +35,24 @@ pub fn random_scene(time: &Option<Range<f64>>) -> HittableList {
 
     for a in -11..11 {
         for b in -11..11 {
-            let choose_mat = rand_f64();
+            let choose_mat = rng.rand_f64();
             let center = Point3::new(
-                a as f64 + 0.9 * rand_f64(),
+                a as f64 + 0.9 * rng.rand_f64(),
                 0.2,
-                b as f64 + 0.9 * rand_f64(),
+                b as f64 + 0.9 * rng.rand_f64(),
             );
 
             if (center - Point3::new(4.0, 0.2, 0.0)).magnitude() > 0.9 {
                 let sphere_mat: Arc<dyn Material + Send + Sync>;
                 if choose_mat < 0.8 {
-                    let albedo = random_vec() * random_vec();
+                    let albedo = random_vec(&mut rng) * random_vec(&mut rng);
                     sphere_mat = Arc::new(Lambertian::new(albedo));
-                    let end_point = center + Point3::new(0.0, rand_f64_range(0.0, 0.5), 0.0);
-                    let mvmt = if let Some(t) = time {
-                        Some(Movement::new(t.clone(), end_point))
-                    } else {
-                        None
-                    };
+                    let end_point = center + Point3::new(0.0, rng.rand_f64_range(0.0, 0.5), 0.0);
+                    let mvmt = time.as_ref().map(|t| Movement::new(t.clone(), end_point));
                     world.add(Arc::new(Sphere::new(center, 0.2, sphere_mat, mvmt)));
                 } else if choose_mat < 0.95 {
-                    let albedo = random_vec_range(0.5, 1.0);
-                    let fuzz = rand_f64_range(0.0, 0.5);
+                    let albedo = random_vec_range(&mut rng, 0.5, 1.0);
+                    let fuzz = rng.rand_f64_range(0.0, 0.5);
                     sphere_mat = Arc::new(Metal::new(albedo, fuzz));
                     world.add(Arc::new(Sphere::new(center, 0.2, sphere_mat, None)));
                 } else {
@@ -78,3 +89,468 @@ pub fn random_scene(time: &Option<Range<f64>>) -> HittableList {
 
     world
 }
+
+pub fn two_spheres() -> HittableList {
+    let mut world = HittableList::new();
+    let checker = Arc::new(CheckerTexture::from_colors(
+        0.8,
+        Color::new(0.2, 0.3, 0.1),
+        Color::new(0.9, 0.9, 0.9),
+    ));
+
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, -10.0, 0.0),
+        10.0,
+        Arc::new(Lambertian::from_texture(checker.clone())),
+        None,
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, 10.0, 0.0),
+        10.0,
+        Arc::new(Lambertian::from_texture(checker)),
+        None,
+    )));
+
+    world
+}
+
+pub fn earth(texture_path: impl AsRef<Path>) -> Result<HittableList, ImageTextureError> {
+    let earth_texture = Arc::new(ImageTexture::open(
+        texture_path,
+        TextureFilter::Bilinear,
+        WrapMode::Repeat,
+        ColorSpace::Srgb,
+    )?);
+    let earth_surface = Arc::new(Lambertian::from_texture(earth_texture));
+    let globe = Arc::new(Sphere::new(
+        Point3::new(0.0, 0.0, 0.0),
+        2.0,
+        earth_surface,
+        None,
+    ));
+
+    let mut world = HittableList::new();
+    world.add(globe);
+    Ok(world)
+}
+
+pub fn two_perlin_spheres(seed: u64) -> HittableList {
+    let mut world = HittableList::new();
+    let noise = Arc::new(NoiseTexture::new(seed, 4.0, 7));
+    let marble = Arc::new(MarbleTexture::new(
+        seed,
+        4.0,
+        7,
+        10.0,
+        Color::new(1.0, 1.0, 1.0),
+        Color::new(0.0, 0.0, 0.0),
+    ));
+    let wood = Arc::new(WoodTexture::new(
+        seed,
+        6.0,
+        4,
+        0.4,
+        Color::new(0.76, 0.56, 0.34),
+        Color::new(0.36, 0.2, 0.09),
+    ));
+
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Lambertian::from_texture(noise)),
+        None,
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, 2.0, -1.5),
+        2.0,
+        Arc::new(Lambertian::from_texture(marble)),
+        None,
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, 2.0, 2.5),
+        2.0,
+        Arc::new(Lambertian::from_texture(wood)),
+        None,
+    )));
+
+    world
+}
+
+/// Returns the scene and, separately, the light in it for the renderer to sample.
+pub fn simple_light(seed: u64) -> (HittableList, HittableList) {
+    let mut world = HittableList::new();
+    let marble = Arc::new(MarbleTexture::new(
+        seed,
+        4.0,
+        7,
+        10.0,
+        Color::new(1.0, 1.0, 1.0),
+        Color::new(0.0, 0.0, 0.0),
+    ));
+    let marble_mat = Arc::new(Lambertian::from_texture(marble));
+
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        marble_mat.clone(),
+        None,
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, 2.0, 0.0),
+        2.0,
+        marble_mat,
+        None,
+    )));
+
+    let light = Arc::new(DiffuseLight::new(Color::new(4.0, 4.0, 4.0)));
+    let light = Arc::new(Sphere::new(Point3::new(0.0, 7.0, 0.0), 2.0, light, None));
+    world.add(light.clone());
+
+    let mut lights = HittableList::new();
+    lights.add(light);
+
+    (world, lights)
+}
+
+/// Returns the scene and, separately, the ceiling light for the renderer to sample.
+pub fn cornell_box() -> (HittableList, HittableList) {
+    let mut world = HittableList::new();
+
+    let red = Arc::new(Lambertian::new(Color::new(0.65, 0.05, 0.05)));
+    let white = Arc::new(Lambertian::new(Color::new(0.73, 0.73, 0.73)));
+    let green = Arc::new(Lambertian::new(Color::new(0.12, 0.45, 0.15)));
+    let light = Arc::new(DiffuseLight::new(Color::new(15.0, 15.0, 15.0)));
+
+    world.add(Arc::new(Quad::yz_rect(
+        0.0..555.0,
+        0.0..555.0,
+        555.0,
+        green,
+    )));
+    world.add(Arc::new(Quad::yz_rect(0.0..555.0, 0.0..555.0, 0.0, red)));
+    let light = Arc::new(Quad::xz_rect(213.0..343.0, 227.0..332.0, 554.0, light));
+    world.add(light.clone());
+    let mut lights = HittableList::new();
+    lights.add(light);
+    world.add(Arc::new(Quad::xz_rect(
+        0.0..555.0,
+        0.0..555.0,
+        0.0,
+        white.clone(),
+    )));
+    world.add(Arc::new(Quad::xz_rect(
+        0.0..555.0,
+        0.0..555.0,
+        555.0,
+        white.clone(),
+    )));
+    world.add(Arc::new(Quad::xy_rect(
+        0.0..555.0,
+        0.0..555.0,
+        555.0,
+        white.clone(),
+    )));
+
+    let box1 = Arc::new(Cuboid::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(165.0, 330.0, 165.0),
+        white.clone(),
+    ));
+    world.add(Arc::new(Transform::new(
+        box1,
+        Mat4::<f64>::translation_3d(Vec3::new(265.0, 0.0, 295.0))
+            * Mat4::rotation_y(15.0_f64.to_radians()),
+    )));
+
+    let box2 = Arc::new(Cuboid::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(165.0, 165.0, 165.0),
+        white,
+    ));
+    world.add(Arc::new(Transform::new(
+        box2,
+        Mat4::<f64>::translation_3d(Vec3::new(130.0, 0.0, 65.0))
+            * Mat4::rotation_y((-18.0_f64).to_radians()),
+    )));
+
+    (world, lights)
+}
+
+/// Returns the scene and, separately, the ceiling light for the renderer to sample.
+pub fn cornell_smoke() -> (HittableList, HittableList) {
+    let mut world = HittableList::new();
+
+    let red = Arc::new(Lambertian::new(Color::new(0.65, 0.05, 0.05)));
+    let white = Arc::new(Lambertian::new(Color::new(0.73, 0.73, 0.73)));
+    let green = Arc::new(Lambertian::new(Color::new(0.12, 0.45, 0.15)));
+    let light = Arc::new(DiffuseLight::new(Color::new(7.0, 7.0, 7.0)));
+
+    world.add(Arc::new(Quad::yz_rect(
+        0.0..555.0,
+        0.0..555.0,
+        555.0,
+        green,
+    )));
+    world.add(Arc::new(Quad::yz_rect(0.0..555.0, 0.0..555.0, 0.0, red)));
+    let light = Arc::new(Quad::xz_rect(113.0..443.0, 127.0..432.0, 554.0, light));
+    world.add(light.clone());
+    let mut lights = HittableList::new();
+    lights.add(light);
+    world.add(Arc::new(Quad::xz_rect(
+        0.0..555.0,
+        0.0..555.0,
+        555.0,
+        white.clone(),
+    )));
+    world.add(Arc::new(Quad::xz_rect(
+        0.0..555.0,
+        0.0..555.0,
+        0.0,
+        white.clone(),
+    )));
+    world.add(Arc::new(Quad::xy_rect(
+        0.0..555.0,
+        0.0..555.0,
+        555.0,
+        white.clone(),
+    )));
+
+    let box1 = Arc::new(Cuboid::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(165.0, 330.0, 165.0),
+        white.clone(),
+    ));
+    let box1 = Arc::new(Transform::new(
+        box1,
+        Mat4::<f64>::translation_3d(Vec3::new(265.0, 0.0, 295.0))
+            * Mat4::rotation_y(15.0_f64.to_radians()),
+    ));
+
+    let box2 = Arc::new(Cuboid::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(165.0, 165.0, 165.0),
+        white,
+    ));
+    let box2 = Arc::new(Transform::new(
+        box2,
+        Mat4::<f64>::translation_3d(Vec3::new(130.0, 0.0, 65.0))
+            * Mat4::rotation_y((-18.0_f64).to_radians()),
+    ));
+
+    world.add(Arc::new(ConstantMedium::new(
+        box1,
+        0.01,
+        Arc::new(Isotropic::new(Color::new(0.0, 0.0, 0.0))),
+    )));
+    world.add(Arc::new(ConstantMedium::new(
+        box2,
+        0.01,
+        Arc::new(HenyeyGreenstein::new(Color::new(1.0, 1.0, 1.0), 0.6)),
+    )));
+
+    (world, lights)
+}
+
+/// A row of rough metals and rough glass on a checkered floor under an area light. Returns the
+/// scene and, separately, the light for the renderer to sample.
+pub fn microfacet_spheres() -> (HittableList, HittableList) {
+    let mut world = HittableList::new();
+    let checker = Arc::new(CheckerTexture::from_colors(
+        1.0,
+        Color::new(0.2, 0.2, 0.2),
+        Color::new(0.8, 0.8, 0.8),
+    ));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Lambertian::from_texture(checker)),
+        None,
+    )));
+
+    let materials: [Arc<dyn Material + Send + Sync>; 5] = [
+        Arc::new(RoughConductor::new(
+            ComplexIor::gold(),
+            MicrofacetDistribution::Ggx,
+            0.2,
+        )),
+        Arc::new(RoughConductor::new(
+            ComplexIor::copper(),
+            MicrofacetDistribution::Beckmann,
+            0.35,
+        )),
+        Arc::new(RoughConductor::new(
+            ComplexIor::aluminium(),
+            MicrofacetDistribution::Ggx,
+            0.5,
+        )),
+        Arc::new(RoughConductor::new(
+            ComplexIor::silver(),
+            MicrofacetDistribution::Ggx,
+            0.05,
+        )),
+        Arc::new(RoughDielectric::new(1.5, MicrofacetDistribution::Ggx, 0.25)),
+    ];
+    for (i, material) in materials.iter().enumerate() {
+        world.add(Arc::new(Sphere::new(
+            Point3::new(-4.4 + 2.2 * i as f64, 1.0, 0.0),
+            1.0,
+            material.clone(),
+            None,
+        )));
+    }
+
+    let light = Arc::new(DiffuseLight::new(Color::new(6.0, 6.0, 6.0)));
+    let light = Arc::new(Quad::xz_rect(-3.0..3.0, -3.0..3.0, 8.0, light));
+    world.add(light.clone());
+    let mut lights = HittableList::new();
+    lights.add(light);
+
+    (world, lights)
+}
+
+/// The principled material in a few of its guises: plastic, brushed metal, cloth, lacquered paint,
+/// frosted glass, and a plastic whose roughness comes from a checker texture. Returns the scene
+/// and, separately, the light for the renderer to sample.
+pub fn principled_spheres() -> (HittableList, HittableList) {
+    let mut world = HittableList::new();
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        None,
+    )));
+
+    let plastic = PrincipledParams::new(Color::new(0.8, 0.1, 0.1));
+
+    let mut brushed = PrincipledParams::new(Color::new(0.9, 0.9, 0.9));
+    brushed.metallic = 1.0.into();
+    brushed.roughness = 0.4.into();
+    brushed.anisotropic = 0.9.into();
+
+    let mut cloth = PrincipledParams::new(Color::new(0.2, 0.2, 0.6));
+    cloth.roughness = 1.0.into();
+    cloth.specular = 0.1.into();
+    cloth.sheen = 1.0.into();
+
+    let mut paint = PrincipledParams::new(Color::new(0.1, 0.3, 0.1));
+    paint.clearcoat = 1.0.into();
+    paint.clearcoat_roughness = 0.05.into();
+
+    let mut glass = PrincipledParams::new(Color::new(0.9, 1.0, 0.95));
+    glass.roughness = 0.2.into();
+    glass.transmission = 1.0.into();
+
+    let mut patterned = PrincipledParams::new(Color::new(0.9, 0.6, 0.2));
+    patterned.roughness = Param::Texture(Arc::new(UvCheckerTexture::from_colors(
+        8.0,
+        4.0,
+        Color::new(0.1, 0.1, 0.1),
+        Color::new(0.8, 0.8, 0.8),
+    )));
+
+    let materials = [plastic, brushed, cloth, paint, glass, patterned];
+    for (i, params) in materials.iter().enumerate() {
+        world.add(Arc::new(Sphere::new(
+            Point3::new(-5.5 + 2.2 * i as f64, 1.0, 0.0),
+            1.0,
+            Arc::new(Principled::new(params.clone())),
+            None,
+        )));
+    }
+
+    let light = Arc::new(DiffuseLight::new(Color::new(6.0, 6.0, 6.0)));
+    let light = Arc::new(Quad::xz_rect(-3.0..3.0, -3.0..3.0, 8.0, light));
+    world.add(light.clone());
+    let mut lights = HittableList::new();
+    lights.add(light);
+
+    (world, lights)
+}
+
+/// Bump maps on the floor, a sphere and a rotated box, and a normal map on a mirrored sphere, all
+/// lit from above.
+pub fn surface_detail() -> (HittableList, HittableList) {
+    let mut world = HittableList::new();
+
+    //ripples spreading out from under the sphere in the middle
+    let ripples = Arc::new(WoodTexture::new(
+        5,
+        2.0,
+        4,
+        0.5,
+        Color::new(1.0, 1.0, 1.0),
+        Color::new(0.0, 0.0, 0.0),
+    ));
+    let floor = Arc::new(NormalMapped::bump(
+        Arc::new(Lambertian::new(Color::new(0.6, 0.55, 0.5))),
+        ripples,
+        0.06,
+    ));
+    world.add(Arc::new(Quad::new(
+        Point3::new(-10.0, 0.0, 10.0),
+        Vec3::new(20.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, -20.0),
+        floor,
+    )));
+
+    let stucco = Arc::new(NormalMapped::bump(
+        Arc::new(Lambertian::new(Color::new(0.8, 0.3, 0.2))),
+        Arc::new(NoiseTexture::new(7, 4.0, 7)),
+        0.1,
+    ));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(-2.5, 1.0, 0.0),
+        1.0,
+        stucco,
+        None,
+    )));
+
+    //a normal map of two tilted normals in a checker, which makes the sphere look faceted
+    let facets = Arc::new(UvCheckerTexture::from_colors(
+        16.0,
+        8.0,
+        Color::new(0.8, 0.5, 0.9),
+        Color::new(0.5, 0.2, 0.9),
+    ));
+    let faceted = Arc::new(NormalMapped::normal_map(
+        Arc::new(Metal::new(Color::new(0.8, 0.8, 0.9), 0.0)),
+        facets,
+    ));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, 1.0, 0.0),
+        1.0,
+        faceted,
+        None,
+    )));
+
+    let carved = Arc::new(NormalMapped::bump(
+        Arc::new(Lambertian::new(Color::new(0.9, 0.9, 0.85))),
+        Arc::new(MarbleTexture::new(
+            3,
+            4.0,
+            7,
+            5.0,
+            Color::new(0.0, 0.0, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+        )),
+        0.05,
+    ));
+    let block = Arc::new(Cuboid::new(
+        Point3::new(-0.75, 0.0, -0.75),
+        Point3::new(0.75, 1.5, 0.75),
+        carved,
+    ));
+    world.add(Arc::new(Transform::new(
+        block,
+        Mat4::<f64>::translation_3d(Vec3::new(2.5, 0.0, 0.0))
+            * Mat4::rotation_y(35.0_f64.to_radians()),
+    )));
+
+    let light = Arc::new(DiffuseLight::new(Color::new(8.0, 8.0, 8.0)));
+    let light = Arc::new(Quad::xz_rect(-2.0..2.0, -1.0..3.0, 6.0, light));
+    world.add(light.clone());
+    let mut lights = HittableList::new();
+    lights.add(light);
+
+    (world, lights)
+}
@@ -1,7 +1,9 @@
 use crate::aabb::AABB;
 use crate::hittable::{HitRecord, Hittable};
 use crate::material::Material;
+use crate::onb::Onb;
 use crate::ray::Ray;
+use crate::sampler::Sampler;
 use crate::Point3;
 use std::ops::Range;
 use std::sync::Arc;
@@ -53,9 +55,43 @@ impl Sphere {
     }
 }
 
+impl Sphere {
+    /// Maps a point on the unit sphere to (u, v), with u running around the y axis from
+    /// x = -1 and v running from the south pole to the north pole.
+    fn get_sphere_uv(p: Point3) -> (f64, f64) {
+        let theta = (-p.y).clamp(-1.0, 1.0).acos();
+        let phi = (-p.z).atan2(p.x) + std::f64::consts::PI;
+
+        (
+            phi / (2.0 * std::f64::consts::PI),
+            theta / std::f64::consts::PI,
+        )
+    }
+
+    /// How the hit point moves with the u and v of `get_sphere_uv`, given the outward normal
+    /// there. Both vanish at the poles, where u is undefined.
+    fn uv_derivatives(&self, n: Vec3<f64>) -> (Vec3<f64>, Vec3<f64>) {
+        let pi = std::f64::consts::PI;
+        let dpdu = 2.0 * pi * self.radius * Vec3::new(n.z, 0.0, -n.x);
+        //sin theta, where theta is the angle from the south pole
+        let sin_theta = (1.0 - n.y * n.y).max(0.0).sqrt();
+        let dpdv = if sin_theta > 0.0 {
+            pi * self.radius * Vec3::new(-n.x * n.y / sin_theta, sin_theta, -n.z * n.y / sin_theta)
+        } else {
+            Vec3::zero()
+        };
+        (dpdu, dpdv)
+    }
+
+    //lights are sampled where they are at the start of their movement
+    fn light_time(&self) -> f64 {
+        self.movement.as_ref().map_or(0.0, |mvmt| mvmt.time.start)
+    }
+}
+
 impl Hittable for Sphere {
     fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
-        let oc = if let Some(_) = self.movement {
+        let oc = if self.movement.is_some() {
             r.origin - self.center(r.time)
         } else {
             r.origin - self.center
@@ -78,24 +114,32 @@ impl Hittable for Sphere {
             }
         }
         let p = r.at(root);
-        let outward_normal = if let Some(_) = self.movement {
+        let outward_normal = if self.movement.is_some() {
             //maybe roll the 2 if lets into 1 somehow
-            p - self.center(r.time) / self.radius
+            (p - self.center(r.time)) / self.radius
         } else {
             (p - self.center) / self.radius
         };
+        let (u, v) = Sphere::get_sphere_uv(outward_normal);
+        let (tangent, bitangent) = self.uv_derivatives(outward_normal);
+        let normal = HitRecord::set_face_normal(r, outward_normal);
 
         Some(HitRecord {
             t: root,
-            p: p,
-            normal: HitRecord::set_face_normal(r, outward_normal),
+            u,
+            v,
+            p,
+            normal,
+            geometric_normal: normal,
+            tangent,
+            bitangent,
             mat_ptr: self.mat_ptr.clone(),
             front_face: Vec3::dot(r.direction, outward_normal) < 0.0,
         })
     }
 
     fn bounding_box(&self, time: Range<f64>) -> Option<AABB> {
-        let output = if let Some(_) = self.movement {
+        let output = if self.movement.is_some() {
             let box1 = AABB::new(
                 self.center(time.start) - Point3::new(self.radius, self.radius, self.radius),
                 self.center(time.start) + Point3::new(self.radius, self.radius, self.radius),
@@ -113,4 +157,37 @@ impl Hittable for Sphere {
         };
         Some(output)
     }
+
+    //directions are drawn uniformly from the cone that the sphere fills as seen from origin
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3<f64>) -> f64 {
+        let time = self.light_time();
+        if self
+            .hit(&Ray::new(*origin, *direction, time), 0.001, f64::INFINITY)
+            .is_none()
+        {
+            return 0.0;
+        }
+        let distance_squared = (self.center(time) - *origin).magnitude_squared();
+        let radius_squared = self.radius * self.radius;
+        if distance_squared <= radius_squared {
+            return 0.0;
+        }
+        let cos_theta_max = (1.0 - radius_squared / distance_squared).sqrt();
+        let solid_angle = 2.0 * std::f64::consts::PI * (1.0 - cos_theta_max);
+        1.0 / solid_angle
+    }
+
+    fn random(&self, origin: &Point3, sampler: &mut dyn Sampler) -> Vec3<f64> {
+        let to_center = self.center(self.light_time()) - *origin;
+        let distance_squared = to_center.magnitude_squared();
+        let cos_theta_max = (1.0 - self.radius * self.radius / distance_squared)
+            .max(0.0)
+            .sqrt();
+
+        let xi = sampler.get_2d();
+        let cos_theta = 1.0 + xi.y * (cos_theta_max - 1.0);
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * std::f64::consts::PI * xi.x;
+        Onb::from_w(to_center).spherical(cos_theta, sin_theta, phi)
+    }
 }
@@ -0,0 +1,373 @@
+use crate::perlin::Perlin;
+use crate::Color;
+use crate::Point3;
+use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+pub trait Texture {
+    fn value(&self, u: f64, v: f64, p: &Point3) -> Color;
+}
+
+pub struct SolidColor {
+    color_value: Color,
+}
+
+impl SolidColor {
+    pub fn new(color_value: Color) -> Self {
+        Self { color_value }
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _u: f64, _v: f64, _p: &Point3) -> Color {
+        self.color_value
+    }
+}
+
+/// Alternates between two textures on a grid of cubes in world space.
+pub struct CheckerTexture {
+    inv_scale: f64,
+    even: Arc<dyn Texture + Send + Sync>,
+    odd: Arc<dyn Texture + Send + Sync>,
+}
+
+impl CheckerTexture {
+    pub fn new(
+        scale: f64,
+        even: Arc<dyn Texture + Send + Sync>,
+        odd: Arc<dyn Texture + Send + Sync>,
+    ) -> Self {
+        Self {
+            inv_scale: 1.0 / scale,
+            even,
+            odd,
+        }
+    }
+
+    pub fn from_colors(scale: f64, even: Color, odd: Color) -> Self {
+        Self::new(
+            scale,
+            Arc::new(SolidColor::new(even)),
+            Arc::new(SolidColor::new(odd)),
+        )
+    }
+}
+
+impl Texture for CheckerTexture {
+    fn value(&self, u: f64, v: f64, p: &Point3) -> Color {
+        let cells = (self.inv_scale * *p).map(|x| x.floor() as i64);
+        if (cells.x + cells.y + cells.z) % 2 == 0 {
+            self.even.value(u, v, p)
+        } else {
+            self.odd.value(u, v, p)
+        }
+    }
+}
+
+/// Alternates between two textures on a grid laid out in the surface's UV coordinates.
+pub struct UvCheckerTexture {
+    width: f64,
+    height: f64,
+    even: Arc<dyn Texture + Send + Sync>,
+    odd: Arc<dyn Texture + Send + Sync>,
+}
+
+impl UvCheckerTexture {
+    /// `width` and `height` are the number of checks across the full 0 to 1 range of u and v.
+    pub fn new(
+        width: f64,
+        height: f64,
+        even: Arc<dyn Texture + Send + Sync>,
+        odd: Arc<dyn Texture + Send + Sync>,
+    ) -> Self {
+        Self {
+            width,
+            height,
+            even,
+            odd,
+        }
+    }
+
+    pub fn from_colors(width: f64, height: f64, even: Color, odd: Color) -> Self {
+        Self::new(
+            width,
+            height,
+            Arc::new(SolidColor::new(even)),
+            Arc::new(SolidColor::new(odd)),
+        )
+    }
+}
+
+impl Texture for UvCheckerTexture {
+    fn value(&self, u: f64, v: f64, p: &Point3) -> Color {
+        let cell_u = (u * self.width).floor() as i64;
+        let cell_v = (v * self.height).floor() as i64;
+        if (cell_u + cell_v) % 2 == 0 {
+            self.even.value(u, v, p)
+        } else {
+            self.odd.value(u, v, p)
+        }
+    }
+}
+
+/// Grayscale turbulence.
+pub struct NoiseTexture {
+    noise: Perlin,
+    scale: f64,
+    octaves: usize,
+}
+
+impl NoiseTexture {
+    pub fn new(seed: u64, scale: f64, octaves: usize) -> Self {
+        Self {
+            noise: Perlin::new(seed),
+            scale,
+            octaves,
+        }
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _u: f64, _v: f64, p: &Point3) -> Color {
+        Color::one() * self.noise.turb(&(self.scale * *p), self.octaves)
+    }
+}
+
+/// Veins running along z, bent by turbulence.
+pub struct MarbleTexture {
+    noise: Perlin,
+    scale: f64,
+    octaves: usize,
+    turbulence: f64,
+    light: Color,
+    dark: Color,
+}
+
+impl MarbleTexture {
+    pub fn new(
+        seed: u64,
+        scale: f64,
+        octaves: usize,
+        turbulence: f64,
+        light: Color,
+        dark: Color,
+    ) -> Self {
+        Self {
+            noise: Perlin::new(seed),
+            scale,
+            octaves,
+            turbulence,
+            light,
+            dark,
+        }
+    }
+}
+
+impl Texture for MarbleTexture {
+    fn value(&self, _u: f64, _v: f64, p: &Point3) -> Color {
+        let phase = self.scale * p.z + self.turbulence * self.noise.turb(p, self.octaves);
+        let t = 0.5 * (1.0 + phase.sin());
+        Color::lerp(self.dark, self.light, t)
+    }
+}
+
+/// Growth rings around the y axis, wobbled by turbulence.
+pub struct WoodTexture {
+    noise: Perlin,
+    rings: f64,
+    octaves: usize,
+    turbulence: f64,
+    light: Color,
+    dark: Color,
+}
+
+impl WoodTexture {
+    /// `rings` is the number of rings per unit of distance from the axis.
+    pub fn new(
+        seed: u64,
+        rings: f64,
+        octaves: usize,
+        turbulence: f64,
+        light: Color,
+        dark: Color,
+    ) -> Self {
+        Self {
+            noise: Perlin::new(seed),
+            rings,
+            octaves,
+            turbulence,
+            light,
+            dark,
+        }
+    }
+}
+
+impl Texture for WoodTexture {
+    fn value(&self, _u: f64, _v: f64, p: &Point3) -> Color {
+        let radius = (p.x * p.x + p.z * p.z).sqrt();
+        let r = self.rings * radius + self.turbulence * self.noise.turb(p, self.octaves);
+        let ring = r - r.floor();
+        //sharpen the ring edges so the late wood reads as a thin dark band
+        let t = ring.powf(3.0);
+        Color::lerp(self.light, self.dark, t)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextureFilter {
+    Nearest,
+    Bilinear,
+}
+
+/// How texel lookups outside of the image are brought back inside it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WrapMode {
+    Clamp,
+    Repeat,
+    Mirror,
+}
+
+/// The encoding of the values stored in an image file.
+///
+/// Color maps are almost always `Srgb`, while data such as normal or roughness maps is `Linear`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorSpace {
+    Srgb,
+    Linear,
+}
+
+#[derive(Debug)]
+pub enum ImageTextureError {
+    Load(PathBuf, image::ImageError),
+    /// The image decoded fine but is zero pixels wide or high, so there is nothing to sample.
+    Empty(PathBuf),
+}
+
+impl fmt::Display for ImageTextureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Load(path, e) => write!(f, "could not load {}: {}", path.display(), e),
+            Self::Empty(path) => write!(f, "{} has no pixels", path.display()),
+        }
+    }
+}
+
+impl Error for ImageTextureError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Load(_, e) => Some(e),
+            Self::Empty(_) => None,
+        }
+    }
+}
+
+/// A texture backed by a PNG, JPEG or PPM file, stored as linear color.
+pub struct ImageTexture {
+    width: usize,
+    height: usize,
+    data: Vec<Color>,
+    filter: TextureFilter,
+    wrap: WrapMode,
+}
+
+impl ImageTexture {
+    pub fn open(
+        path: impl AsRef<Path>,
+        filter: TextureFilter,
+        wrap: WrapMode,
+        color_space: ColorSpace,
+    ) -> Result<Self, ImageTextureError> {
+        let path = path.as_ref();
+        let img = image::open(path)
+            .map_err(|e| ImageTextureError::Load(path.to_path_buf(), e))?
+            .to_rgb32f();
+        if img.width() == 0 || img.height() == 0 {
+            return Err(ImageTextureError::Empty(path.to_path_buf()));
+        }
+
+        let data = img
+            .pixels()
+            .map(|p| {
+                let c = Color::new(p[0] as f64, p[1] as f64, p[2] as f64);
+                match color_space {
+                    ColorSpace::Srgb => c.map(srgb_to_linear),
+                    ColorSpace::Linear => c,
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            width: img.width() as usize,
+            height: img.height() as usize,
+            data,
+            filter,
+            wrap,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    fn wrap_index(&self, i: i64, n: usize) -> usize {
+        let n = n as i64;
+        let i = match self.wrap {
+            WrapMode::Clamp => i.clamp(0, n - 1),
+            WrapMode::Repeat => i.rem_euclid(n),
+            WrapMode::Mirror => {
+                let m = i.rem_euclid(2 * n);
+                if m >= n {
+                    2 * n - 1 - m
+                } else {
+                    m
+                }
+            }
+        };
+        i as usize
+    }
+
+    fn texel(&self, x: i64, y: i64) -> Color {
+        let x = self.wrap_index(x, self.width);
+        let y = self.wrap_index(y, self.height);
+        self.data[y * self.width + x]
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _p: &Point3) -> Color {
+        //image rows run top to bottom while v runs bottom to top
+        let x = u * self.width as f64;
+        let y = (1.0 - v) * self.height as f64;
+
+        match self.filter {
+            TextureFilter::Nearest => self.texel(x.floor() as i64, y.floor() as i64),
+            TextureFilter::Bilinear => {
+                let x = x - 0.5;
+                let y = y - 0.5;
+                let x0 = x.floor();
+                let y0 = y.floor();
+                let tx = x - x0;
+                let ty = y - y0;
+                let (x0, y0) = (x0 as i64, y0 as i64);
+
+                let top = (1.0 - tx) * self.texel(x0, y0) + tx * self.texel(x0 + 1, y0);
+                let bottom = (1.0 - tx) * self.texel(x0, y0 + 1) + tx * self.texel(x0 + 1, y0 + 1);
+                (1.0 - ty) * top + ty * bottom
+            }
+        }
+    }
+}
+
+fn srgb_to_linear(c: f64) -> f64 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
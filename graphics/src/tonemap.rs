@@ -0,0 +1,78 @@
+use crate::Color;
+
+/// Compresses linear radiance into [0, 1] before it is encoded for display.
+///
+/// Every operator works on each channel separately.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ToneMap {
+    /// Clips everything above 1, which is what the renderer always did.
+    #[default]
+    Clamp,
+    /// `c / (1 + c)`, which never quite reaches white.
+    Reinhard,
+    /// Reinhard scaled so that `white` maps exactly to 1.
+    ReinhardExtended { white: f64 },
+    /// John Hable's filmic curve from Uncharted 2, normalized so that `white` maps to 1.
+    Hable { white: f64 },
+    /// Krzysztof Narkowicz's fit of the ACES reference rendering transform.
+    Aces,
+}
+
+impl ToneMap {
+    pub fn apply(&self, color: Color) -> Color {
+        match *self {
+            ToneMap::Clamp => color,
+            ToneMap::Reinhard => color.map(|c| c / (1.0 + c)),
+            ToneMap::ReinhardExtended { white } => {
+                let inv_white_sq = 1.0 / (white * white);
+                color.map(|c| c * (1.0 + c * inv_white_sq) / (1.0 + c))
+            }
+            ToneMap::Hable { white } => {
+                let inv_white = 1.0 / hable_partial(white);
+                color.map(|c| hable_partial(c) * inv_white)
+            }
+            ToneMap::Aces => color.map(aces_fitted),
+        }
+        .map(|c| c.clamp(0.0, 1.0))
+    }
+}
+
+//the constants from Hable's "Filmic Tonemapping Operators" talk
+fn hable_partial(x: f64) -> f64 {
+    const A: f64 = 0.15; //shoulder strength
+    const B: f64 = 0.50; //linear strength
+    const C: f64 = 0.10; //linear angle
+    const D: f64 = 0.20; //toe strength
+    const E: f64 = 0.02; //toe numerator
+    const F: f64 = 0.30; //toe denominator
+    ((x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F)) - E / F
+}
+
+fn aces_fitted(x: f64) -> f64 {
+    (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)
+}
+
+/// Encodes tone mapped values in [0, 1] for an 8 or 16 bit display format.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TransferFunction {
+    /// The piecewise sRGB curve, with its linear segment near black.
+    #[default]
+    Srgb,
+    /// A plain power curve, `c^(1 / gamma)`.
+    Gamma(f64),
+}
+
+impl TransferFunction {
+    pub fn encode(&self, c: f64) -> f64 {
+        match *self {
+            TransferFunction::Srgb => {
+                if c <= 0.0031308 {
+                    12.92 * c
+                } else {
+                    1.055 * c.powf(1.0 / 2.4) - 0.055
+                }
+            }
+            TransferFunction::Gamma(gamma) => c.powf(1.0 / gamma),
+        }
+    }
+}
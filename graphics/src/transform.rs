@@ -0,0 +1,117 @@
+use crate::aabb::AABB;
+use crate::hittable::{HitRecord, Hittable};
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::utils::unit_vector;
+use crate::Point3;
+use std::ops::Range;
+use std::sync::Arc;
+use vek::mat::Mat4;
+use vek::vec::Vec3;
+
+/// Places a shared object in the world with an affine matrix, so one mesh can be reused at
+/// several poses without copying it.
+#[derive(Clone)]
+pub struct Transform {
+    object: Arc<dyn Hittable + Send + Sync>,
+    object_to_world: Mat4<f64>,
+    world_to_object: Mat4<f64>,
+    //normals transform with the inverse transpose, which is the transpose of world_to_object
+    normal_to_world: Mat4<f64>,
+    //how much world_to_object scales volumes, used to carry densities over solid angle across
+    det_world_to_object: f64,
+}
+
+impl Transform {
+    /// Panics if `object_to_world` is not invertible.
+    pub fn new(object: Arc<dyn Hittable + Send + Sync>, object_to_world: Mat4<f64>) -> Self {
+        let world_to_object = object_to_world.inverted();
+        assert!(
+            world_to_object
+                .into_row_array()
+                .iter()
+                .all(|x| x.is_finite()),
+            "Transform matrix is not invertible"
+        );
+        Self {
+            object,
+            object_to_world,
+            world_to_object,
+            normal_to_world: world_to_object.transposed(),
+            det_world_to_object: world_to_object.determinant().abs(),
+        }
+    }
+
+    pub fn translate(object: Arc<dyn Hittable + Send + Sync>, offset: Vec3<f64>) -> Self {
+        Self::new(object, Mat4::translation_3d(offset))
+    }
+
+    /// Rotates counter-clockwise by `degrees` about `axis`, looking down the axis at the origin.
+    pub fn rotate(object: Arc<dyn Hittable + Send + Sync>, axis: Vec3<f64>, degrees: f64) -> Self {
+        Self::new(object, Mat4::rotation_3d(degrees.to_radians(), axis))
+    }
+
+    pub fn scale(object: Arc<dyn Hittable + Send + Sync>, factors: Vec3<f64>) -> Self {
+        Self::new(object, Mat4::scaling_3d(factors))
+    }
+
+    pub fn matrix(&self) -> Mat4<f64> {
+        self.object_to_world
+    }
+}
+
+impl Hittable for Transform {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        //the direction is left unnormalized so that t means the same thing in both spaces
+        let object_ray = Ray::new(
+            self.world_to_object.mul_point(r.origin),
+            self.world_to_object.mul_direction(r.direction),
+            r.time,
+        );
+
+        let mut rec = self.object.hit(&object_ray, t_min, t_max)?;
+        rec.p = self.object_to_world.mul_point(rec.p);
+        rec.normal = unit_vector(self.normal_to_world.mul_direction(rec.normal));
+        rec.geometric_normal =
+            unit_vector(self.normal_to_world.mul_direction(rec.geometric_normal));
+        rec.tangent = self.object_to_world.mul_direction(rec.tangent);
+        rec.bitangent = self.object_to_world.mul_direction(rec.bitangent);
+        Some(rec)
+    }
+
+    fn bounding_box(&self, time: Range<f64>) -> Option<AABB> {
+        let bbox = self.object.bounding_box(time)?;
+
+        let mut min = Point3::broadcast(f64::INFINITY);
+        let mut max = Point3::broadcast(f64::NEG_INFINITY);
+        for i in 0..8 {
+            let corner = Point3::new(
+                if i & 1 == 0 { bbox.min.x } else { bbox.max.x },
+                if i & 2 == 0 { bbox.min.y } else { bbox.max.y },
+                if i & 4 == 0 { bbox.min.z } else { bbox.max.z },
+            );
+            let corner = self.object_to_world.mul_point(corner);
+            min = Vec3::partial_min(min, corner);
+            max = Vec3::partial_max(max, corner);
+        }
+
+        Some(AABB::new(min, max))
+    }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3<f64>) -> f64 {
+        let object_direction = self.world_to_object.mul_direction(*direction);
+        let object_pdf = self
+            .object
+            .pdf_value(&self.world_to_object.mul_point(*origin), &object_direction);
+        //a linear map M takes the unit direction w to M w / |M w|, which stretches solid angle
+        //by |det M| / |M w|^3
+        let stretch = object_direction.magnitude() / direction.magnitude();
+        object_pdf * self.det_world_to_object / (stretch * stretch * stretch)
+    }
+
+    fn random(&self, origin: &Point3, sampler: &mut dyn Sampler) -> Vec3<f64> {
+        let object_origin = self.world_to_object.mul_point(*origin);
+        self.object_to_world
+            .mul_direction(self.object.random(&object_origin, sampler))
+    }
+}
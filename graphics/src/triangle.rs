@@ -0,0 +1,191 @@
+use crate::aabb::{AABB, BOX_PADDING};
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::utils::{area_to_solid_angle_pdf, unit_vector};
+use crate::Point3;
+use std::ops::Range;
+use std::sync::Arc;
+use vek::vec::{Vec2, Vec3};
+
+pub struct Triangle {
+    vertices: [Point3; 3],
+    normals: Option<[Vec3<f64>; 3]>,
+    uvs: Option<[Vec2<f64>; 3]>,
+    mat_ptr: Arc<dyn Material + Send + Sync>,
+}
+
+impl Triangle {
+    /// Vertices are wound counter-clockwise when seen from the front face.
+    /// Without per-vertex normals the flat face normal is used, and without
+    /// UVs the barycentric coordinates of the hit are used instead.
+    pub fn new(
+        vertices: [Point3; 3],
+        mat_ptr: Arc<dyn Material + Send + Sync>,
+        normals: Option<[Vec3<f64>; 3]>,
+        uvs: Option<[Vec2<f64>; 3]>,
+    ) -> Self {
+        Self {
+            vertices,
+            normals,
+            uvs,
+            mat_ptr,
+        }
+    }
+
+    fn face_normal(&self) -> Vec3<f64> {
+        let [p0, p1, p2] = self.vertices;
+        unit_vector(Vec3::cross(p1 - p0, p2 - p0))
+    }
+
+    /// How a point on the triangle moves with u and v. Without UVs, or with UVs that collapse
+    /// the triangle, u and v are taken as the barycentric coordinates of the second and third
+    /// vertices.
+    fn uv_derivatives(&self) -> (Vec3<f64>, Vec3<f64>) {
+        let [p0, p1, p2] = self.vertices;
+        if let Some([uv0, uv1, uv2]) = self.uvs {
+            let duv02 = uv0 - uv2;
+            let duv12 = uv1 - uv2;
+            let det = duv02.x * duv12.y - duv02.y * duv12.x;
+            if det.abs() > 1e-12 {
+                let dp02 = p0 - p2;
+                let dp12 = p1 - p2;
+                return (
+                    (duv12.y * dp02 - duv02.y * dp12) / det,
+                    (duv02.x * dp12 - duv12.x * dp02) / det,
+                );
+            }
+        }
+        (p1 - p0, p2 - p0)
+    }
+}
+
+fn max_dimension(v: Vec3<f64>) -> usize {
+    let v = v.map(f64::abs);
+    if v.x > v.y && v.x > v.z {
+        0
+    } else if v.y > v.z {
+        1
+    } else {
+        2
+    }
+}
+
+impl Hittable for Triangle {
+    //watertight intersection from Woop, Benthin and Wald, "Watertight Ray/Triangle Intersection" (2013).
+    //the ray is sheared so that it points down +z, which makes the edge tests exact on shared edges
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let kz = max_dimension(r.direction);
+        let mut kx = (kz + 1) % 3;
+        let mut ky = (kx + 1) % 3;
+        if r.direction[kz] < 0.0 {
+            std::mem::swap(&mut kx, &mut ky);
+        }
+
+        let sx = r.direction[kx] / r.direction[kz];
+        let sy = r.direction[ky] / r.direction[kz];
+        let sz = 1.0 / r.direction[kz];
+
+        let a = self.vertices[0] - r.origin;
+        let b = self.vertices[1] - r.origin;
+        let c = self.vertices[2] - r.origin;
+
+        let ax = a[kx] - sx * a[kz];
+        let ay = a[ky] - sy * a[kz];
+        let bx = b[kx] - sx * b[kz];
+        let by = b[ky] - sy * b[kz];
+        let cx = c[kx] - sx * c[kz];
+        let cy = c[ky] - sy * c[kz];
+
+        //scaled barycentric coordinates, each one is the edge function of the opposite edge
+        let e0 = cx * by - cy * bx;
+        let e1 = ax * cy - ay * cx;
+        let e2 = bx * ay - by * ax;
+
+        if (e0 < 0.0 || e1 < 0.0 || e2 < 0.0) && (e0 > 0.0 || e1 > 0.0 || e2 > 0.0) {
+            return None;
+        }
+        let det = e0 + e1 + e2;
+        if det == 0.0 {
+            return None;
+        }
+
+        let t_scaled = e0 * sz * a[kz] + e1 * sz * b[kz] + e2 * sz * c[kz];
+        let t = t_scaled / det;
+        if t < t_min || t > t_max {
+            return None;
+        }
+
+        let b0 = e0 / det;
+        let b1 = e1 / det;
+        let b2 = e2 / det;
+
+        let p = b0 * self.vertices[0] + b1 * self.vertices[1] + b2 * self.vertices[2];
+        let geometric_normal = self.face_normal();
+        let front_face = Vec3::dot(r.direction, geometric_normal) < 0.0;
+
+        let shading_normal = if let Some([n0, n1, n2]) = self.normals {
+            unit_vector(b0 * n0 + b1 * n1 + b2 * n2)
+        } else {
+            geometric_normal
+        };
+        let (normal, geometric_normal) = if front_face {
+            (shading_normal, geometric_normal)
+        } else {
+            (-shading_normal, -geometric_normal)
+        };
+        let (tangent, bitangent) = self.uv_derivatives();
+
+        let uv = if let Some([uv0, uv1, uv2]) = self.uvs {
+            b0 * uv0 + b1 * uv1 + b2 * uv2
+        } else {
+            Vec2::new(b1, b2)
+        };
+
+        Some(HitRecord {
+            p,
+            normal,
+            geometric_normal,
+            tangent,
+            bitangent,
+            mat_ptr: self.mat_ptr.clone(),
+            t,
+            u: uv.x,
+            v: uv.y,
+            front_face,
+        })
+    }
+
+    fn bounding_box(&self, _time: Range<f64>) -> Option<AABB> {
+        let [p0, p1, p2] = self.vertices;
+        Some(
+            AABB::new(
+                Vec3::partial_min(Vec3::partial_min(p0, p1), p2),
+                Vec3::partial_max(Vec3::partial_max(p0, p1), p2),
+            )
+            .padded(BOX_PADDING),
+        )
+    }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3<f64>) -> f64 {
+        match self.hit(&Ray::new(*origin, *direction, 0.0), 0.001, f64::INFINITY) {
+            Some(rec) => {
+                let [p0, p1, p2] = self.vertices;
+                let area = 0.5 * Vec3::cross(p1 - p0, p2 - p0).magnitude();
+                area_to_solid_angle_pdf(area, self.face_normal(), *direction, rec.t)
+            }
+            None => 0.0,
+        }
+    }
+
+    fn random(&self, origin: &Point3, sampler: &mut dyn Sampler) -> Vec3<f64> {
+        let [p0, p1, p2] = self.vertices;
+        let xi = sampler.get_2d();
+        //the square root keeps the points uniform instead of bunching up at p0
+        let su = xi.x.sqrt();
+        let b0 = 1.0 - su;
+        let b1 = xi.y * su;
+        b0 * p0 + b1 * p1 + (1.0 - b0 - b1) * p2 - *origin
+    }
+}
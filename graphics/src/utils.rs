@@ -1,53 +1,63 @@
-use rand::{thread_rng, Rng};
+use crate::rng::Rng;
+use crate::sampler::Sampler;
+use crate::Color;
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI};
 use vek::vec::Vec3;
 
 pub fn unit_vector(vec: Vec3<f64>) -> Vec3<f64> {
     vec / vec.magnitude()
 }
 
-pub fn random_vec_range(min: f64, max: f64) -> Vec3<f64> {
-    let mut rng = thread_rng();
-    Vec3::new(
-        rng.gen_range(min..max),
-        rng.gen_range(min..max),
-        rng.gen_range(min..max),
-    )
+/// Turns the density of a point drawn uniformly over a flat area into a density over the solid
+/// angle seen from where `direction` starts, given that the point lies `t` along it.
+pub fn area_to_solid_angle_pdf(area: f64, normal: Vec3<f64>, direction: Vec3<f64>, t: f64) -> f64 {
+    let distance_squared = t * t * direction.magnitude_squared();
+    let cosine = Vec3::dot(direction, normal).abs() / direction.magnitude();
+    distance_squared / (cosine * area)
 }
 
-pub fn random_vec() -> Vec3<f64> {
-    random_vec_range(0.0, 1.0)
+pub fn luminance(color: Color) -> f64 {
+    0.2126 * color.x + 0.7152 * color.y + 0.0722 * color.z
 }
 
-pub fn random_in_unit_sphere() -> Vec3<f64> {
-    loop {
-        let p = random_vec_range(-1.0, 1.0);
-        if p.magnitude_squared() >= 1.0 {
-            continue;
-        }
-        return p;
-    }
+pub fn random_vec_range(rng: &mut Rng, min: f64, max: f64) -> Vec3<f64> {
+    Vec3::new(
+        rng.rand_f64_range(min, max),
+        rng.rand_f64_range(min, max),
+        rng.rand_f64_range(min, max),
+    )
 }
 
-pub fn random_in_unit_disk() -> Vec3<f64> {
-    let mut rng = thread_rng();
-    loop {
-        let p = Vec3::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), 0.0);
-        if p.magnitude_squared() >= 1.0 {
-            continue;
-        }
-        return p;
-    }
+pub fn random_vec(rng: &mut Rng) -> Vec3<f64> {
+    random_vec_range(rng, 0.0, 1.0)
 }
 
-pub fn random_unit_vector() -> Vec3<f64> {
-    unit_vector(random_in_unit_sphere())
+/// Uniform inside the unit ball, from three sample dimensions.
+pub fn random_in_unit_sphere(sampler: &mut dyn Sampler) -> Vec3<f64> {
+    let direction = random_unit_vector(sampler);
+    direction * sampler.get_1d().cbrt()
 }
-pub fn rand_f64() -> f64 {
-    let mut rng = thread_rng();
-    rng.gen_range(0.0..1.0)
+
+/// Uniform inside the unit disk in the xy plane, using Shirley and Chiu's concentric mapping so
+/// that well-spread samples stay well spread.
+pub fn random_in_unit_disk(sampler: &mut dyn Sampler) -> Vec3<f64> {
+    let offset = sampler.get_2d() * 2.0 - 1.0;
+    if offset.x == 0.0 && offset.y == 0.0 {
+        return Vec3::zero();
+    }
+    let (r, theta) = if offset.x.abs() > offset.y.abs() {
+        (offset.x, FRAC_PI_4 * (offset.y / offset.x))
+    } else {
+        (offset.y, FRAC_PI_2 - FRAC_PI_4 * (offset.x / offset.y))
+    };
+    Vec3::new(r * theta.cos(), r * theta.sin(), 0.0)
 }
 
-pub fn rand_f64_range(min: f64, max: f64) -> f64 {
-    let mut rng = thread_rng();
-    rng.gen_range(min..max)
+/// Uniform on the unit sphere, from two sample dimensions.
+pub fn random_unit_vector(sampler: &mut dyn Sampler) -> Vec3<f64> {
+    let u = sampler.get_2d();
+    let z = 1.0 - 2.0 * u.x;
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * PI * u.y;
+    Vec3::new(r * phi.cos(), r * phi.sin(), z)
 }
@@ -1,12 +1,11 @@
+use graphics::bvh::{BvhNode, SplitMethod};
 use graphics::camera::Camera;
 use graphics::hittable_list::HittableList;
-use graphics::image::Image;
-use graphics::material::{Dielectric, Lambertian};
+use graphics::image::{Image, RenderSettings};
 use graphics::scenes::random_scene;
-use graphics::sphere::Sphere;
-use graphics::Color;
 use graphics::Point3;
-use std::sync::Arc;
+use std::env;
+use std::process;
 use vek::vec::Vec3;
 fn main() {
     let time = Some(0.0..1.0);
@@ -16,10 +15,17 @@ fn main() {
     const IMAGE_HEIGHT: usize = ((IMAGE_WIDTH as f64) / ASPECT_RATIO) as usize;
     const SAMPLES_PER_PIXEL: usize = 100;
     const MAX_DEPTH: usize = 50;
+    //the same seed always gives the same scene and image
+    const SEED: u64 = 0;
 
     //world setup
 
-    let world = random_scene(&time);
+    let world = BvhNode::new(
+        &random_scene(&time, SEED),
+        time.clone().unwrap_or(0.0..0.0),
+        SplitMethod::SurfaceAreaHeuristic,
+    )
+    .expect("every object in the scene has a bounding box");
 
     //camera setup
 
@@ -39,13 +45,16 @@ fn main() {
         time,
     );
 
-    let im = Image::render_scene(
-        IMAGE_WIDTH,
-        IMAGE_HEIGHT,
-        SAMPLES_PER_PIXEL,
-        MAX_DEPTH,
-        cam,
-        world,
-    );
-    println!("{}", im);
+    let mut settings = RenderSettings::new(IMAGE_WIDTH, IMAGE_HEIGHT, SAMPLES_PER_PIXEL, MAX_DEPTH);
+    settings.seed = SEED;
+    let im = Image::render_scene(&settings, cam, world, HittableList::new());
+    //write to the given file, or print a PPM to stdout when no path is given
+    if let Some(path) = env::args().nth(1) {
+        if let Err(e) = im.save(&path) {
+            eprintln!("could not save {}: {}", path, e);
+            process::exit(1);
+        }
+    } else {
+        println!("{}", im);
+    }
 }